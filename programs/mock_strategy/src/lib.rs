@@ -0,0 +1,149 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+declare_id!("CR3sdxtM9VstZEBtXrbFSTbTAFoM5tTKRHF9D8Jc74XC");
+
+pub const STRATEGY_STATE_SEED: &[u8] = b"strategy_state";
+
+/// Minimal strategy implementing the simple_vault adapter interface
+/// (`deposit`, `withdraw`, `total_assets`). Funds just sit in a token account
+/// owned by the strategy state PDA; yield is simulated by sending tokens to it.
+#[program]
+pub mod mock_strategy {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>, vault: Pubkey) -> Result<()> {
+        let state = &mut ctx.accounts.strategy_state;
+        state.vault = vault;
+        state.asset_mint = ctx.accounts.asset_mint.key();
+        state.token_account = ctx.accounts.strategy_token_account.key();
+        state.bump = ctx.bumps.strategy_state;
+        Ok(())
+    }
+
+    pub fn deposit(ctx: Context<StrategyDeposit>, amount: u64) -> Result<()> {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            to: ctx.accounts.strategy_token_account.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, amount)
+    }
+
+    pub fn withdraw(ctx: Context<StrategyWithdraw>, amount: u64) -> Result<()> {
+        let state = &ctx.accounts.strategy_state;
+        let seeds: &[&[u8]] = &[STRATEGY_STATE_SEED, state.vault.as_ref(), &[state.bump]];
+        let signer = &[seeds];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.strategy_token_account.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.strategy_state.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        );
+        token::transfer(cpi_ctx, amount)
+    }
+
+    pub fn total_assets(ctx: Context<StrategyTotalAssets>) -> Result<u64> {
+        Ok(ctx.accounts.strategy_token_account.amount)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct StrategyState {
+    pub vault: Pubkey,
+    pub asset_mint: Pubkey,
+    pub token_account: Pubkey,
+    pub bump: u8,
+}
+
+#[derive(Accounts)]
+#[instruction(vault: Pubkey)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub asset_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + StrategyState::INIT_SPACE,
+        seeds = [STRATEGY_STATE_SEED, vault.as_ref()],
+        bump
+    )]
+    pub strategy_state: Account<'info, StrategyState>,
+
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = asset_mint,
+        associated_token::authority = strategy_state,
+    )]
+    pub strategy_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Account order below is the adapter interface simple_vault builds its CPIs against.
+
+#[derive(Accounts)]
+pub struct StrategyDeposit<'info> {
+    #[account(address = strategy_state.vault)]
+    pub vault: Signer<'info>,
+
+    #[account(mut)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [STRATEGY_STATE_SEED, strategy_state.vault.as_ref()],
+        bump = strategy_state.bump
+    )]
+    pub strategy_state: Account<'info, StrategyState>,
+
+    #[account(mut, address = strategy_state.token_account)]
+    pub strategy_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct StrategyWithdraw<'info> {
+    #[account(address = strategy_state.vault)]
+    pub vault: Signer<'info>,
+
+    #[account(mut)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [STRATEGY_STATE_SEED, strategy_state.vault.as_ref()],
+        bump = strategy_state.bump
+    )]
+    pub strategy_state: Account<'info, StrategyState>,
+
+    #[account(mut, address = strategy_state.token_account)]
+    pub strategy_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct StrategyTotalAssets<'info> {
+    #[account(
+        seeds = [STRATEGY_STATE_SEED, strategy_state.vault.as_ref()],
+        bump = strategy_state.bump
+    )]
+    pub strategy_state: Account<'info, StrategyState>,
+
+    #[account(address = strategy_state.token_account)]
+    pub strategy_token_account: Account<'info, TokenAccount>,
+}
@@ -0,0 +1,308 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+use anchor_lang::system_program::{create_account, transfer, CreateAccount, Transfer};
+
+declare_id!("qGN7Qr6P4JdYCEtjnjuMseT74G3bAN2b8v555SWCDE7");
+
+pub const BRIDGE_SEED: &[u8] = b"Bridge";
+pub const FEE_COLLECTOR_SEED: &[u8] = b"fee_collector";
+pub const SEQUENCE_SEED: &[u8] = b"Sequence";
+pub const POSTED_VAA_SEED: &[u8] = b"PostedVAA";
+pub const MESSAGE_PREFIX: &[u8] = b"msg";
+pub const POSTED_VAA_PREFIX: &[u8] = b"vaa";
+
+pub const SOLANA_CHAIN_ID: u16 = 1;
+
+/// Minimal stand-in for the Wormhole core bridge. `post_message` takes the
+/// same instruction data and accounts, charges the bridge fee the same way
+/// and writes the same `Bridge`, `Sequence` and posted message layouts, so
+/// an emitter can be tested against it without guardians or a mainnet clone.
+/// `post_vaa` writes a `PostedVAA` account as if guardians had signed it.
+#[program]
+pub mod mock_wormhole {
+    use super::*;
+
+    #[instruction(discriminator = [0])]
+    pub fn initialize(ctx: Context<Initialize>, fee: u64) -> Result<()> {
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let rent = Rent::get()?;
+
+        // The core bridge's fee collector is a bare system account
+        let fee_collector_rent = rent.minimum_balance(0);
+        transfer(
+            CpiContext::new(
+                system_program.clone(),
+                Transfer {
+                    from: payer.clone(),
+                    to: ctx.accounts.fee_collector.to_account_info(),
+                },
+            ),
+            fee_collector_rent,
+        )?;
+
+        let data = BridgeData {
+            guardian_set_index: 0,
+            last_lamports: fee_collector_rent,
+            config: BridgeConfig {
+                guardian_set_expiration_time: 0,
+                fee,
+            },
+        }
+        .try_to_vec()?;
+        create_owned(
+            &payer,
+            &ctx.accounts.bridge,
+            &system_program,
+            &[BRIDGE_SEED, &[ctx.bumps.bridge]],
+            &data,
+        )
+    }
+
+    #[instruction(discriminator = [1])]
+    pub fn post_message(
+        ctx: Context<PostMessage>,
+        nonce: u32,
+        payload: Vec<u8>,
+        consistency_level: u8,
+    ) -> Result<()> {
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+
+        // The fee must already sit in the collector, on top of what it held
+        // after the previous message
+        let mut bridge = BridgeData::deserialize(&mut &ctx.accounts.bridge.data.borrow()[..])?;
+        let fee_collector = ctx.accounts.fee_collector.lamports();
+        require!(
+            fee_collector.saturating_sub(bridge.last_lamports) >= bridge.config.fee,
+            MockWormholeError::InsufficientFees
+        );
+        bridge.last_lamports = fee_collector;
+        bridge.serialize(&mut &mut ctx.accounts.bridge.data.borrow_mut()[..])?;
+
+        let emitter = ctx.accounts.emitter.key();
+        let sequence_info = ctx.accounts.sequence.to_account_info();
+        if sequence_info.data_is_empty() {
+            create_owned(
+                &payer,
+                &sequence_info,
+                &system_program,
+                &[SEQUENCE_SEED, emitter.as_ref(), &[ctx.bumps.sequence]],
+                &SequenceTracker { sequence: 0 }.try_to_vec()?,
+            )?;
+        }
+        let mut tracker = SequenceTracker::deserialize(&mut &sequence_info.data.borrow()[..])?;
+
+        let message = MessageData {
+            vaa_version: 0,
+            consistency_level,
+            vaa_time: 0,
+            vaa_signature_account: Pubkey::default(),
+            submission_time: Clock::get()?.unix_timestamp as u32,
+            nonce,
+            sequence: tracker.sequence,
+            emitter_chain: SOLANA_CHAIN_ID,
+            emitter_address: emitter.to_bytes(),
+            payload,
+        };
+        let mut data = MESSAGE_PREFIX.to_vec();
+        message.serialize(&mut data)?;
+        create_owned(&payer, &ctx.accounts.message, &system_program, &[], &data)?;
+
+        tracker.sequence += 1;
+        tracker.serialize(&mut &mut sequence_info.data.borrow_mut()[..])?;
+        Ok(())
+    }
+
+    /// Stores a foreign message at the core bridge's `[POSTED_VAA_SEED,
+    /// body hash]` address. Unlike the real post_vaa, no guardian signatures
+    /// are checked.
+    #[allow(clippy::too_many_arguments)]
+    #[instruction(discriminator = [2])]
+    pub fn post_vaa(
+        ctx: Context<PostVaa>,
+        vaa_time: u32,
+        nonce: u32,
+        emitter_chain: u16,
+        emitter_address: [u8; 32],
+        sequence: u64,
+        consistency_level: u8,
+        payload: Vec<u8>,
+    ) -> Result<()> {
+        let message = MessageData {
+            vaa_version: 1,
+            consistency_level,
+            vaa_time,
+            vaa_signature_account: Pubkey::default(),
+            submission_time: 0,
+            nonce,
+            sequence,
+            emitter_chain,
+            emitter_address,
+            payload,
+        };
+        let hash = message.body_hash();
+        let (address, bump) = Pubkey::find_program_address(&[POSTED_VAA_SEED, &hash], &crate::ID);
+        require_keys_eq!(
+            ctx.accounts.posted_vaa.key(),
+            address,
+            MockWormholeError::InvalidPostedVaa
+        );
+
+        let mut data = POSTED_VAA_PREFIX.to_vec();
+        message.serialize(&mut data)?;
+        create_owned(
+            &ctx.accounts.payer,
+            &ctx.accounts.posted_vaa,
+            &ctx.accounts.system_program,
+            &[POSTED_VAA_SEED, &hash, &[bump]],
+            &data,
+        )
+    }
+}
+
+/// Creates a `data`-sized account owned by this program and fills it. Empty
+/// `seeds` means `account` already signed the instruction.
+fn create_owned<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    seeds: &[&[u8]],
+    data: &[u8],
+) -> Result<()> {
+    let signer = &[seeds];
+    let signers: &[&[&[u8]]] = if seeds.is_empty() { &[] } else { signer };
+    create_account(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            CreateAccount {
+                from: payer.clone(),
+                to: account.clone(),
+            },
+            signers,
+        ),
+        Rent::get()?.minimum_balance(data.len()),
+        data.len() as u64,
+        &crate::ID,
+    )?;
+    account.data.borrow_mut().copy_from_slice(data);
+    Ok(())
+}
+
+// Core bridge account layouts: plain borsh, no discriminator.
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct BridgeConfig {
+    pub guardian_set_expiration_time: u32,
+    pub fee: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct BridgeData {
+    pub guardian_set_index: u32,
+    pub last_lamports: u64,
+    pub config: BridgeConfig,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct SequenceTracker {
+    pub sequence: u64,
+}
+
+/// Stored after `MESSAGE_PREFIX` in posted messages and after
+/// `POSTED_VAA_PREFIX` in posted VAAs.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct MessageData {
+    pub vaa_version: u8,
+    pub consistency_level: u8,
+    pub vaa_time: u32,
+    pub vaa_signature_account: Pubkey,
+    pub submission_time: u32,
+    pub nonce: u32,
+    pub sequence: u64,
+    pub emitter_chain: u16,
+    pub emitter_address: [u8; 32],
+    pub payload: Vec<u8>,
+}
+
+impl MessageData {
+    /// keccak256 of the signed VAA body, all integers big-endian.
+    pub fn body_hash(&self) -> [u8; 32] {
+        let mut body = Vec::with_capacity(51 + self.payload.len());
+        body.extend_from_slice(&self.vaa_time.to_be_bytes());
+        body.extend_from_slice(&self.nonce.to_be_bytes());
+        body.extend_from_slice(&self.emitter_chain.to_be_bytes());
+        body.extend_from_slice(&self.emitter_address);
+        body.extend_from_slice(&self.sequence.to_be_bytes());
+        body.push(self.consistency_level);
+        body.extend_from_slice(&self.payload);
+        keccak::hash(&body).to_bytes()
+    }
+}
+
+#[error_code]
+pub enum MockWormholeError {
+    #[msg("Bridge fee was not paid into the fee collector")]
+    InsufficientFees,
+
+    #[msg("Posted VAA account does not match the message hash")]
+    InvalidPostedVaa,
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Created here with the core bridge's `BridgeData` layout
+    #[account(mut, seeds = [BRIDGE_SEED], bump)]
+    pub bridge: UncheckedAccount<'info>,
+
+    /// CHECK: Bare system account that collects message fees
+    #[account(mut, seeds = [FEE_COLLECTOR_SEED], bump)]
+    pub fee_collector: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Same account order as the core bridge's post_message.
+
+#[derive(Accounts)]
+pub struct PostMessage<'info> {
+    /// CHECK: `BridgeData`, written by `initialize`
+    #[account(mut, seeds = [BRIDGE_SEED], bump, owner = crate::ID)]
+    pub bridge: UncheckedAccount<'info>,
+
+    /// Fresh account for the posted message, signed by the caller
+    #[account(mut)]
+    pub message: Signer<'info>,
+
+    pub emitter: Signer<'info>,
+
+    /// CHECK: The emitter's `SequenceTracker`, created on its first message
+    #[account(mut, seeds = [SEQUENCE_SEED, emitter.key().as_ref()], bump)]
+    pub sequence: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Bare system account that collects message fees
+    #[account(mut, seeds = [FEE_COLLECTOR_SEED], bump)]
+    pub fee_collector: UncheckedAccount<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PostVaa<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: `[POSTED_VAA_SEED, body hash]`, checked against the message
+    #[account(mut)]
+    pub posted_vaa: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
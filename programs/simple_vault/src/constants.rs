@@ -6,14 +6,31 @@ pub const JUMP_MULTIPLIER: u64 = 1_090_000_000;
 pub const INITIAL_BORROW_INDEX: u64 = 1_000_000_000;
 pub const MAX_RESERVE_FACTOR: u64 = 500_000_000;
 pub const PRECISION: u64 = 1_000_000_000;
+pub const BPS_DENOMINATOR: u64 = 10_000;
 pub const VAULT_SEED: &[u8] = b"vault_v2";
 pub const USER_SHARES_SEED: &[u8] = b"user_shares_v2";
 pub const USER_INFO_SEED: &[u8] = b"user_info_v2";
+pub const STRATEGY_SEED: &[u8] = b"strategy_v2";
+pub const MAX_STRATEGIES: usize = 5;
+pub const WITHDRAWAL_REQUEST_SEED: &[u8] = b"withdrawal_request_v2";
+pub const ALLOWLIST_SEED: &[u8] = b"allowlist_v2";
+pub const OPERATOR_SEED: &[u8] = b"operator_v2";
+pub const POSITION_ESCROW_SEED: &[u8] = b"position_escrow_v2";
+pub const CLAIMED_VAA_SEED: &[u8] = b"claimed_vaa_v2";
+pub const VAULT_VERSION: u8 = 2;
+pub const USER_INFO_VERSION: u8 = 1;
+// Operator scopes
+pub const SCOPE_LOCK: u8 = 1 << 0;
+pub const SCOPE_RELOCK: u8 = 1 << 1;
+pub const SCOPE_HARVEST: u8 = 1 << 2;
+pub const SCOPE_ALL: u8 = SCOPE_LOCK | SCOPE_RELOCK | SCOPE_HARVEST;
 pub const LOCKED_YIELD_MULTIPLIER: u64 = 50_000_000;
 pub const SCALE: u64 = 1_000_000;
 pub const SCALE_U128: u128 = 1_000_000;
 pub const MAX_EXTENSION_RATIO: u64 = 1_000_000;
 pub const MIN_EXTENSION_RATIO: u64 = 100_000;
+pub const MAX_MANAGEMENT_FEE_BPS: u64 = 500; // 5% per year
+pub const MAX_PERFORMANCE_FEE_BPS: u64 = 5000; // 50% of gains
 pub const EARLY_WITHDRAWAL_PENALTY: u64 = 100_000;  // 0.10
     
 pub const SECONDS_PER_DAY: i64 = 86400;
@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::math::*;
+
+// ================================
+// STREAMING FEES
+// ================================
+//
+// Management fee: annual rate on total assets, charged pro rata for the time
+// since the last accrual.
+// Performance fee: share of the gain in price-per-share above the high-water
+// mark. Both are taken by minting new shares to the fee recipient, so the
+// vault's assets never leave the vault.
+
+#[derive(Debug, Default, PartialEq)]
+pub struct FeeAccrual {
+    pub management_fee: u64,
+    pub performance_fee: u64,
+    pub fee_shares: u64,
+    pub price_per_share: u64,
+    pub high_water_mark: u64,
+}
+
+/// Price of one share scaled by `PRECISION`. An empty vault prices at 1.0.
+pub fn price_per_share(total_assets: u64, total_shares: u64) -> Result<u64> {
+    if total_shares == 0 {
+        return Ok(PRECISION);
+    }
+    mul_div(total_assets, PRECISION, total_shares, Rounding::Down)
+}
+
+/// Fees only ever mint shares to the recipient, so they round down.
+pub fn calculate_fee_accrual(
+    total_assets: u64,
+    total_shares: u64,
+    high_water_mark: u64,
+    management_fee_bps: u64,
+    performance_fee_bps: u64,
+    elapsed: i64,
+) -> Result<FeeAccrual> {
+    let current_pps = price_per_share(total_assets, total_shares)?;
+    if total_shares == 0 || total_assets == 0 {
+        return Ok(FeeAccrual {
+            price_per_share: current_pps,
+            high_water_mark,
+            ..Default::default()
+        });
+    }
+
+    let elapsed = to_u64(elapsed.max(0))?;
+    let management_fee = mul_div(
+        mul_div(
+            total_assets,
+            management_fee_bps,
+            BPS_DENOMINATOR,
+            Rounding::Down,
+        )?,
+        elapsed,
+        SECONDS_PER_YEAR as u64,
+        Rounding::Down,
+    )?;
+
+    let performance_fee = if current_pps > high_water_mark {
+        let profit = mul_div(
+            current_pps - high_water_mark,
+            total_shares,
+            PRECISION,
+            Rounding::Down,
+        )?;
+        mul_div(profit, performance_fee_bps, BPS_DENOMINATOR, Rounding::Down)?
+    } else {
+        0
+    };
+
+    // Never hand out the whole vault
+    let fee_assets = management_fee
+        .saturating_add(performance_fee)
+        .min(total_assets - 1);
+
+    // Mint enough shares that the recipient ends up owning `fee_assets`
+    let fee_shares = mul_div(
+        fee_assets,
+        total_shares,
+        total_assets - fee_assets,
+        Rounding::Down,
+    )?;
+
+    let price_after = price_per_share(total_assets, checked_add(total_shares, fee_shares)?)?;
+
+    Ok(FeeAccrual {
+        management_fee,
+        performance_fee,
+        fee_shares,
+        price_per_share: price_after,
+        high_water_mark: high_water_mark.max(price_after),
+    })
+}
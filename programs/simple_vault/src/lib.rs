@@ -1,11 +1,31 @@
-use anchor_lang::{prelude::*, Result};
+use anchor_lang::{prelude::*, system_program, Result};
 use anchor_spl::associated_token::AssociatedToken;
-use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount, Transfer};
-use solana_program::{clock::Clock, program_option::COption};
-use unique_low::Collection;
+use anchor_spl::metadata::{Metadata, MetadataAccount};
+use anchor_spl::token::{self, Burn, CloseAccount, Mint, MintTo, Token, TokenAccount, Transfer};
+use solana_program::clock::Clock;
+use unique_low::{
+    payload::{Message, PositionSnapshot},
+    program::UniqueLow,
+    Collection, ForeignEmitter, PostedMessageData, UniqueIdRecord, FOREIGN_EMITTER_SEED,
+    POSITION_AUTHORITY_SEED, UNIQUE_ID_SEED, WORMHOLE_POSTED_VAA_SEED,
+};
 pub mod constants;
+pub mod fees;
+pub mod math;
+pub mod strategy;
 use constants::*;
+use fees::*;
+use math::*;
 use std::convert::TryFrom;
+use strategy::*;
+
+/// `msg!` that is compiled out unless the `debug-logs` feature is enabled.
+macro_rules! debug_msg {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "debug-logs")]
+        msg!($($arg)*);
+    };
+}
 
 declare_id!("DGXrmuhPvYJEWytSpZPB3PCA2zNvSsNvctkAeS924473");
 
@@ -32,21 +52,50 @@ pub mod simple_vault {
         vault.total_locked_shares = 0;
         //-----------------
         vault.nft_collection_address = nft_collection_address; // collection PDA
+        vault.nft_collection_mint = unique_low::collection_mint_address();
         vault.bump = ctx.bumps.vault;
+        vault.risk_manager = ctx.accounts.owner.key();
+        vault.total_debt = 0;
+        vault.strategies = Vec::new();
+        vault.queue_head = 0;
+        vault.queue_tail = 0;
+        vault.total_queued_assets = 0;
+        vault.fee_recipient = ctx.accounts.owner.key();
+        vault.management_fee_bps = 0;
+        vault.performance_fee_bps = 0;
+        vault.high_water_mark = PRECISION;
+        vault.limits = VaultLimits::default();
+        vault.tier_locked_shares = [0; 4];
+        vault.epoch_start = clock.unix_timestamp;
+        vault.epoch_withdrawn = 0;
+        vault.compliance_authority = ctx.accounts.owner.key();
+        vault.allowlist_enabled = false;
+        vault.version = VAULT_VERSION;
+        vault.event_seq = 0;
+
+        emit!(VaultInitialized {
+            vault: vault.key(),
+            owner: vault.owner,
+            asset_mint: vault.asset_mint,
+            share_mint: vault.share_mint,
+            nft_collection: nft_collection_address,
+            share_price: price_per_share(0, 0)?,
+            seq: vault.next_event_seq()?,
+        });
         Ok(())
     }
 
     pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
         let vault = &mut ctx.accounts.vault;
-        let total_assets = ctx.accounts.vault_token_account.amount;
+        vault.check_allowlist(ctx.accounts.allowlist_entry.is_some())?;
+        let balance_before = ctx.accounts.vault_token_account.amount;
+        charge_fees(vault, balance_before, Clock::get()?.unix_timestamp)?;
+        let total_assets = get_total_assets(balance_before, vault)?;
 
-        msg!("Before token transfer");
-        msg!("Deposit amount {:?}", amount);
-        msg!("Vault total shares {:?}", vault.total_shares);
-        msg!(
-            "Pre deposit assets{:?}",
-            ctx.accounts.vault_token_account.amount
-        );
+        debug_msg!("Before token transfer");
+        debug_msg!("Deposit amount {:?}", amount);
+        debug_msg!("Vault total shares {:?}", vault.total_shares);
+        debug_msg!("Pre deposit assets{:?}", total_assets);
 
         let cpi_accounts = Transfer {
             from: ctx.accounts.user_asset_token.to_account_info(),
@@ -56,51 +105,58 @@ pub mod simple_vault {
         let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
         token::transfer(cpi_ctx, amount)?;
 
+        // Price the deposit on what actually arrived, not on what was requested
+        ctx.accounts.vault_token_account.reload()?;
+        let received =
+            calculate_received_amount(balance_before, ctx.accounts.vault_token_account.amount)?;
+
         let fee_bps = get_deposit_fee_bps(&LockTier::Unlocked);
-        let fee_amount = (amount as u128 * fee_bps as u128) / 10000;
-        let net_deposit = amount - fee_amount as u64;
+        let (fee_amount, net_deposit) = calculate_deposit_fee(received, fee_bps)?;
 
-        vault.total_reserves += fee_amount as u64;
+        vault.total_reserves = checked_add(vault.total_reserves, fee_amount)?;
 
-        msg!(
-            "Deposit fee: {} bps, Fee amount: {}, Net deposit: {}",
+        debug_msg!(
+            "Received: {}, Deposit fee: {} bps, Fee amount: {}, Net deposit: {}",
+            received,
             fee_bps,
             fee_amount,
             net_deposit
         );
 
-        let shares_to_mint = if vault.total_shares == 0 {
-            net_deposit
-        } else {
-            if total_assets == 0 {
-                net_deposit
-            } else {
-                msg!("After token transfer");
-                msg!("Net deposit amount {:?}", net_deposit);
-                msg!("Vault total shares {:?}", vault.total_shares);
-                msg!("Pre deposit assets{:?}", total_assets);
+        let shares_to_mint =
+            calculate_shares_to_mint(net_deposit, vault.total_shares, total_assets)?;
 
-                ((net_deposit as u128) * (vault.total_shares as u128) / (total_assets as u128))
-                    as u64
-            }
-        };
+        let total_assets_after = checked_add(total_assets, received)?;
+        vault.check_deposit_cap(total_assets_after)?;
+        vault.check_position_size(
+            checked_add(ctx.accounts.user_info.shares, shares_to_mint)?,
+            total_assets_after,
+            checked_add(vault.total_shares, shares_to_mint)?,
+        )?;
+
+        // Depositing unlocked moves the whole position out of its lock tier
+        vault.release_tier_shares(
+            &ctx.accounts.user_info.lock_tier,
+            ctx.accounts.user_info.shares,
+        )?;
 
         // Mint shares to user
         let asset_mint_key = ctx.accounts.asset_mint.key();
         let seeds: &[&[u8]] = &[
-            VAULT_SEED.as_ref(),
+            VAULT_SEED,
             asset_mint_key.as_ref(),
             vault.owner.as_ref(),
             &[vault.bump],
         ];
-        let signer = &[&seeds[..]];
+        let signer = &[seeds];
 
         // Update user info
         let user_info = &mut ctx.accounts.user_info;
         user_info.vault = vault.key();
-        user_info.deposit_amount += net_deposit;
+        user_info.deposit_amount = checked_add(user_info.deposit_amount, net_deposit)?;
         user_info.nft_mint = ctx.accounts.user_nft_mint.key();
-        user_info.shares += shares_to_mint;
+        user_info.shares = checked_add(user_info.shares, shares_to_mint)?;
+        user_info.version = USER_INFO_VERSION;
         user_info.locked_until = 0;
         user_info.lock_tier = LockTier::Unlocked;
         user_info.deposit_time = Clock::get()?.unix_timestamp;
@@ -118,38 +174,154 @@ pub mod simple_vault {
             shares_to_mint,
         )?;
 
-        vault.total_shares += shares_to_mint;
+        vault.total_shares = checked_add(vault.total_shares, shares_to_mint)?;
 
-        msg!(
+        debug_msg!(
             "Shares minted: {}, Total vault shares: {}",
             shares_to_mint,
             vault.total_shares
         );
 
+        let vault_key = vault.key();
+        let nft_mint = ctx.accounts.user_nft_mint.key();
+        let share_price = price_per_share(total_assets_after, vault.total_shares)?;
+        emit_deposit_fee(vault, vault_key, nft_mint, fee_bps, fee_amount, share_price)?;
+        emit!(Deposited {
+            vault: vault_key,
+            nft_mint,
+            user: ctx.accounts.user.key(),
+            amount: received,
+            fee: fee_amount,
+            shares: shares_to_mint,
+            position_shares: ctx.accounts.user_info.shares,
+            share_price,
+            seq: vault.next_event_seq()?,
+        });
+
         Ok(())
     }
 
-    pub fn lock(ctx: Context<Lock>, amount: u64, tier: u8) -> Result<()> {
+    /// Tops up the position of `nft_mint` from the signer's funds. Anyone may
+    /// call this; the position keeps its tier and lock expiry, and only the
+    /// NFT holder can take the value out again.
+    pub fn deposit_for(ctx: Context<DepositFor>, nft_mint: Pubkey, amount: u64) -> Result<()> {
         let vault = &mut ctx.accounts.vault;
+        vault.check_allowlist(ctx.accounts.allowlist_entry.is_some())?;
+        let balance_before = ctx.accounts.vault_token_account.amount;
+        charge_fees(vault, balance_before, Clock::get()?.unix_timestamp)?;
+        let total_assets = get_total_assets(balance_before, vault)?;
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_asset_token.to_account_info(),
+                    to: ctx.accounts.vault_token_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        ctx.accounts.vault_token_account.reload()?;
+        let received =
+            calculate_received_amount(balance_before, ctx.accounts.vault_token_account.amount)?;
+
         let user_info = &mut ctx.accounts.user_info;
-        let total_assets = ctx.accounts.vault_token_account.amount;
-        let current_time = Clock::get()?.unix_timestamp;
+        let tier = user_info.lock_tier;
+        let fee_bps = get_deposit_fee_bps(&tier);
+        let (fee_amount, net_deposit) = calculate_deposit_fee(received, fee_bps)?;
+        vault.total_reserves = checked_add(vault.total_reserves, fee_amount)?;
+
+        let shares_to_mint =
+            calculate_shares_to_mint(net_deposit, vault.total_shares, total_assets)?;
+
+        let total_assets_after = checked_add(total_assets, received)?;
+        let total_shares_after = checked_add(vault.total_shares, shares_to_mint)?;
+        vault.check_deposit_cap(total_assets_after)?;
+        vault.check_position_size(
+            checked_add(user_info.shares, shares_to_mint)?,
+            total_assets_after,
+            total_shares_after,
+        )?;
+        if tier != LockTier::Unlocked {
+            vault.check_tier_cap(
+                &tier,
+                shares_to_mint,
+                total_assets_after,
+                total_shares_after,
+            )?;
+            vault.book_tier_shares(&tier, shares_to_mint)?;
+        }
 
-        let locktier = LockTier::try_from(tier)?;
+        if user_info.shares == 0 {
+            user_info.deposit_time = Clock::get()?.unix_timestamp;
+        }
+        user_info.vault = vault.key();
+        user_info.nft_mint = nft_mint;
+        user_info.deposit_amount = checked_add(user_info.deposit_amount, net_deposit)?;
+        user_info.shares = checked_add(user_info.shares, shares_to_mint)?;
+        user_info.version = USER_INFO_VERSION;
 
-        let fee_bps = get_deposit_fee_bps(&locktier);
-        let fee_amount = (amount as u128 * fee_bps as u128) / 10000;
-        let net_deposit = amount - fee_amount as u64;
+        let asset_mint_key = ctx.accounts.asset_mint.key();
+        let seeds: &[&[u8]] = &[
+            VAULT_SEED,
+            asset_mint_key.as_ref(),
+            vault.owner.as_ref(),
+            &[vault.bump],
+        ];
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    to: ctx.accounts.user_share_token.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                &[seeds],
+            ),
+            shares_to_mint,
+        )?;
+
+        vault.total_shares = checked_add(vault.total_shares, shares_to_mint)?;
+
+        let vault_key = vault.key();
+        let share_price = price_per_share(total_assets_after, vault.total_shares)?;
+        emit_deposit_fee(vault, vault_key, nft_mint, fee_bps, fee_amount, share_price)?;
+        emit!(DepositedFor {
+            vault: vault_key,
+            nft_mint,
+            funder: ctx.accounts.user.key(),
+            amount: received,
+            fee: fee_amount,
+            shares: shares_to_mint,
+            share_price,
+            seq: vault.next_event_seq()?,
+        });
 
-        vault.total_reserves += fee_amount as u64;
+        Ok(())
+    }
 
-        msg!(
-            "Lock tier: {:?}, Fee: {} bps, Fee amount: {}, Net deposit: {}",
-            tier,
-            fee_bps,
-            fee_amount,
-            net_deposit
-        );
+    /// Callable by the NFT holder or by an operator holding `SCOPE_LOCK`.
+    /// The signer always funds the lock from their own asset account.
+    pub fn lock(ctx: Context<Lock>, amount: u64, tier: u8) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+        authorize_position(
+            &ctx.accounts.user.key(),
+            &ctx.accounts.user_nft_token.owner,
+            ctx.accounts.operator_approval.as_deref(),
+            SCOPE_LOCK,
+            current_time,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.check_allowlist(ctx.accounts.allowlist_entry.is_some())?;
+        let user_info = &mut ctx.accounts.user_info;
+        let balance_before = ctx.accounts.vault_token_account.amount;
+        charge_fees(vault, balance_before, current_time)?;
+        let total_assets = get_total_assets(balance_before, vault)?;
+
+        let locktier = LockTier::try_from(tier)?;
 
         let cpi_accounts = Transfer {
             from: ctx.accounts.user_asset_token.to_account_info(),
@@ -159,20 +331,50 @@ pub mod simple_vault {
         let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
         token::transfer(cpi_ctx, amount)?;
 
-        let shares_to_mint = if vault.total_shares == 0 {
-            net_deposit
-        } else if total_assets == 0 {
+        // Price the deposit on what actually arrived, not on what was requested
+        ctx.accounts.vault_token_account.reload()?;
+        let received =
+            calculate_received_amount(balance_before, ctx.accounts.vault_token_account.amount)?;
+
+        let fee_bps = get_deposit_fee_bps(&locktier);
+        let (fee_amount, net_deposit) = calculate_deposit_fee(received, fee_bps)?;
+
+        vault.total_reserves = checked_add(vault.total_reserves, fee_amount)?;
+
+        debug_msg!(
+            "Lock tier: {:?}, Received: {}, Fee: {} bps, Fee amount: {}, Net deposit: {}",
+            tier,
+            received,
+            fee_bps,
+            fee_amount,
             net_deposit
-        } else {
-            msg!(
-                "Share calculation: net_deposit={}, total_shares={}, total_assets={}",
-                net_deposit,
-                vault.total_shares,
-                total_assets
-            );
-            ((net_deposit as u128) * (vault.total_shares as u128) / (total_assets as u128)) as u64
-        };
+        );
+
+        debug_msg!(
+            "Share calculation: net_deposit={}, total_shares={}, total_assets={}",
+            net_deposit,
+            vault.total_shares,
+            total_assets
+        );
+        let shares_to_mint =
+            calculate_shares_to_mint(net_deposit, vault.total_shares, total_assets)?;
+
+        let total_assets_after = checked_add(total_assets, received)?;
+        let total_shares_after = checked_add(vault.total_shares, shares_to_mint)?;
+        vault.check_deposit_cap(total_assets_after)?;
+        vault.check_position_size(
+            checked_add(user_info.shares, shares_to_mint)?,
+            total_assets_after,
+            total_shares_after,
+        )?;
+        vault.check_tier_cap(
+            &locktier,
+            shares_to_mint,
+            total_assets_after,
+            total_shares_after,
+        )?;
 
+        let old_locked_until = user_info.locked_until;
         if user_info.shares > 0 {
             require!(user_info.lock_tier == locktier, ErrorCode::TierMismatch);
             require!(
@@ -180,10 +382,10 @@ pub mod simple_vault {
                 ErrorCode::InvalidDepositAmount
             );
 
-            let time_remaining = user_info.locked_until - current_time;
+            let time_remaining = checked_sub_i64(user_info.locked_until, current_time)?;
 
             if time_remaining <= 0 {
-                msg!("Position unlocked, calculating ratio-based duration");
+                debug_msg!("Position unlocked, calculating ratio-based duration");
 
                 let new_duration = calculate_ratio_based_duration(
                     user_info.deposit_amount,
@@ -192,45 +394,65 @@ pub mod simple_vault {
                 )?;
 
                 user_info.deposit_time = current_time;
-                user_info.locked_until = current_time + new_duration;
+                user_info.locked_until = checked_add_i64(current_time, new_duration)?;
 
-                msg!("New lock duration: {} seconds (ratio-based)", new_duration);
+                debug_msg!("New lock duration: {} seconds (ratio-based)", new_duration);
             } else {
-                msg!("Position still locked, calculating time-weighted extension");
+                debug_msg!("Position still locked, calculating time-weighted extension");
 
                 let extension_time = calculate_extension(
                     net_deposit,
                     user_info.deposit_amount,
                     time_remaining,
                     get_lock_duration(&locktier),
-                );
+                )?;
 
-                user_info.locked_until = user_info.locked_until + extension_time;
+                user_info.locked_until = checked_add_i64(user_info.locked_until, extension_time)?;
 
-                msg!("Extension: {} seconds (time-weighted)", extension_time);
+                debug_msg!("Extension: {} seconds (time-weighted)", extension_time);
             }
 
-            user_info.deposit_amount += net_deposit;
+            user_info.deposit_amount = checked_add(user_info.deposit_amount, net_deposit)?;
         } else {
             user_info.lock_tier = locktier;
             user_info.deposit_time = current_time;
-            user_info.locked_until = current_time + get_lock_duration(&locktier);
+            user_info.locked_until = checked_add_i64(current_time, get_lock_duration(&locktier))?;
             user_info.deposit_amount = net_deposit;
 
-            msg!("New position created with full duration");
+            debug_msg!("New position created with full duration");
         }
 
-        user_info.shares += shares_to_mint;
-        vault.total_locked_shares += shares_to_mint;
-        vault.total_shares += shares_to_mint;
+        user_info.shares = checked_add(user_info.shares, shares_to_mint)?;
+        user_info.version = USER_INFO_VERSION;
+        vault.book_tier_shares(&locktier, shares_to_mint)?;
+        vault.total_shares = checked_add(vault.total_shares, shares_to_mint)?;
 
-        msg!(
+        debug_msg!(
             "Shares minted: {}, User total shares: {}, Vault total shares: {}",
             shares_to_mint,
             user_info.shares,
             vault.total_shares
         );
 
+        let vault_key = vault.key();
+        let nft_mint = ctx.accounts.user_nft_mint.key();
+        let share_price = price_per_share(total_assets_after, vault.total_shares)?;
+        emit_deposit_fee(vault, vault_key, nft_mint, fee_bps, fee_amount, share_price)?;
+        emit!(Locked {
+            vault: vault_key,
+            nft_mint,
+            user: ctx.accounts.user.key(),
+            tier,
+            amount: received,
+            fee: fee_amount,
+            shares: shares_to_mint,
+            position_shares: user_info.shares,
+            old_locked_until,
+            new_locked_until: user_info.locked_until,
+            share_price,
+            seq: vault.next_event_seq()?,
+        });
+
         Ok(())
     }
 
@@ -248,15 +470,15 @@ pub mod simple_vault {
 
         let vault = &mut ctx.accounts.vault;
 
-        let total_assets = ctx.accounts.vault_token_account.amount;
+        let idle_assets = ctx.accounts.vault_token_account.amount;
+        charge_fees(vault, idle_assets, Clock::get()?.unix_timestamp)?;
+        let total_assets = get_total_assets(idle_assets, vault)?;
 
         let assets_to_withdraw =
-            ((shares as u128) * (total_assets as u128) / (vault.total_shares as u128)) as u64;
+            calculate_assets_for_shares(shares, total_assets, vault.total_shares)?;
 
-        require!(
-            total_assets >= assets_to_withdraw,
-            ErrorCode::InsufficientLiquidity
-        );
+        vault.check_liquidity(idle_assets, assets_to_withdraw)?;
+        vault.record_withdrawal(assets_to_withdraw, Clock::get()?.unix_timestamp)?;
 
         let burn_accounts = Burn {
             mint: ctx.accounts.share_mint.to_account_info(),
@@ -266,11 +488,11 @@ pub mod simple_vault {
 
         let user_nft_mint_key = ctx.accounts.user_nft_mint.key();
         let seeds: &[&[u8]] = &[
-            USER_SHARES_SEED.as_ref(),
+            USER_SHARES_SEED,
             user_nft_mint_key.as_ref(),
             &[ctx.bumps.user_share_pda],
         ];
-        let signer = &[&seeds[..]];
+        let signer = &[seeds];
 
         let burn_ctx = CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
@@ -279,21 +501,22 @@ pub mod simple_vault {
         );
         token::burn(burn_ctx, shares)?;
 
-        user_info.shares -= shares;
-        vault.total_shares -= shares;
+        vault.release_tier_shares(&user_info.lock_tier, shares)?;
+        user_info.shares = checked_sub(user_info.shares, shares)?;
+        vault.total_shares = checked_sub(vault.total_shares, shares)?;
 
         let asset_mint_key = ctx.accounts.asset_mint.key();
         let vault_seeds: &[&[u8]] = &[
-            VAULT_SEED.as_ref(),
+            VAULT_SEED,
             asset_mint_key.as_ref(),
             vault.owner.as_ref(),
             &[vault.bump],
         ];
-        let vault_signer = &[&vault_seeds[..]];
+        let vault_signer = &[vault_seeds];
 
         let cpi_accounts = Transfer {
             from: ctx.accounts.vault_token_account.to_account_info(),
-            to: ctx.accounts.user_asset_token.to_account_info(),
+            to: ctx.accounts.recipient_asset_token.to_account_info(),
             authority: vault.to_account_info(),
         };
 
@@ -305,16 +528,28 @@ pub mod simple_vault {
         token::transfer(cpi_ctx, assets_to_withdraw)?;
 
         emit!(WithdrawEvent {
+            vault: vault.key(),
+            nft_mint: user_nft_mint_key,
             user: ctx.accounts.user.key(),
+            recipient: ctx.accounts.recipient.key(),
             shares,
             amount: assets_to_withdraw,
+            share_price: price_per_share(
+                checked_sub(total_assets, assets_to_withdraw)?,
+                vault.total_shares,
+            )?,
+            seq: vault.next_event_seq()?,
         });
 
         Ok(())
     }
 
     pub fn withdraw_early(ctx: Context<Withdraw>, shares: u64) -> Result<()> {
+        require!(shares > 0, ErrorCode::InvalidAmount);
+
         let user_info = &mut ctx.accounts.user_info;
+        require!(user_info.shares >= shares, ErrorCode::InsufficientShares);
+
         let vault = &mut ctx.accounts.vault;
         let now = Clock::get()?.unix_timestamp;
 
@@ -326,448 +561,4130 @@ pub mod simple_vault {
 
         let penalty_bps = 1000; // 10%
 
-        let total_assets = ctx.accounts.vault_token_account.amount;
+        let idle_assets = ctx.accounts.vault_token_account.amount;
+        charge_fees(vault, idle_assets, now)?;
+        let total_assets = get_total_assets(idle_assets, vault)?;
 
         let assets_to_withdraw =
-            ((shares as u128) * (total_assets as u128) / (vault.total_shares as u128)) as u64;
+            calculate_assets_for_shares(shares, total_assets, vault.total_shares)?;
 
-        require!(
-            total_assets >= assets_to_withdraw,
-            ErrorCode::InsufficientLiquidity
-        );
+        let penalty_amount = mul_div(
+            assets_to_withdraw,
+            penalty_bps,
+            BPS_DENOMINATOR,
+            Rounding::Up,
+        )?;
+        let withdraw_amount = checked_sub(assets_to_withdraw, penalty_amount)?;
+        vault.check_liquidity(idle_assets, withdraw_amount)?;
+        vault.record_withdrawal(withdraw_amount, now)?;
+
+        let user_nft_mint_key = ctx.accounts.user_nft_mint.key();
+        let seeds: &[&[u8]] = &[
+            USER_SHARES_SEED,
+            user_nft_mint_key.as_ref(),
+            &[ctx.bumps.user_share_pda],
+        ];
+        token::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    from: ctx.accounts.user_share_token.to_account_info(),
+                    authority: ctx.accounts.user_share_pda.to_account_info(),
+                },
+                &[seeds],
+            ),
+            shares,
+        )?;
 
-        let penalty_amount = (assets_to_withdraw as u128 * penalty_bps as u128 / 10000) as u64;
-        let withdraw_amount = assets_to_withdraw - penalty_amount;
+        // Penalty stays in the vault as reserves (benefits remaining depositors)
+        vault.total_reserves = checked_add(vault.total_reserves, penalty_amount)?;
 
-        // Penalty goes to vault reserves (benefits remaining depositors)
-        vault.total_reserves += penalty_amount;
+        vault.release_tier_shares(&user_info.lock_tier, shares)?;
+        user_info.shares = checked_sub(user_info.shares, shares)?;
+        vault.total_shares = checked_sub(vault.total_shares, shares)?;
 
-        // Burn shares
-        user_info.shares -= shares;
-        vault.total_shares -= shares;
+        let asset_mint_key = ctx.accounts.asset_mint.key();
+        let vault_seeds: &[&[u8]] = &[
+            VAULT_SEED,
+            asset_mint_key.as_ref(),
+            vault.owner.as_ref(),
+            &[vault.bump],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault_token_account.to_account_info(),
+                    to: ctx.accounts.recipient_asset_token.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                &[vault_seeds],
+            ),
+            withdraw_amount,
+        )?;
 
+        let vault_key = vault.key();
+        let share_price = price_per_share(
+            checked_sub(total_assets, withdraw_amount)?,
+            vault.total_shares,
+        )?;
+        emit!(ReservesChanged {
+            vault: vault_key,
+            nft_mint: user_nft_mint_key,
+            reason: ReserveChangeReason::EarlyWithdrawalPenalty,
+            amount: penalty_amount,
+            total_reserves: vault.total_reserves,
+            share_price,
+            seq: vault.next_event_seq()?,
+        });
         emit!(EarlyWithdrawal {
+            vault: vault_key,
+            nft_mint: user_nft_mint_key,
             user: ctx.accounts.user.key(),
             amount: withdraw_amount,
             penalty: penalty_amount,
-            time_remaining: user_info.locked_until - now,
+            time_remaining: checked_sub_i64(user_info.locked_until, now)?,
+            share_price,
+            seq: vault.next_event_seq()?,
         });
 
         Ok(())
     }
-    pub fn close_vault(ctx: Context<CloseVault>) -> Result<()> {
-        // Vault will be automatically closed and lamports returned to authority
+
+    pub fn request_withdrawal(ctx: Context<RequestWithdrawal>, shares: u64) -> Result<()> {
+        require!(shares > 0, ErrorCode::InvalidAmount);
+
+        let user_info = &mut ctx.accounts.user_info;
+        require!(user_info.shares >= shares, ErrorCode::InsufficientShares);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= user_info.locked_until, ErrorCode::StillLocked);
+
+        // Priced now: the shares are burned and their assets set aside until
+        // the crank pays them out
+        let vault = &mut ctx.accounts.vault;
+        let idle_assets = ctx.accounts.vault_token_account.amount;
+        charge_fees(vault, idle_assets, now)?;
+        let total_assets = get_total_assets(idle_assets, vault)?;
+        let assets = calculate_assets_for_shares(shares, total_assets, vault.total_shares)?;
+        require!(assets > 0, ErrorCode::InvalidAmount);
+        // A request no epoch could pay would block the queue behind it
+        vault.check_withdrawal_size(assets)?;
+
+        let user_nft_mint_key = ctx.accounts.user_nft_mint.key();
+        let seeds: &[&[u8]] = &[
+            USER_SHARES_SEED,
+            user_nft_mint_key.as_ref(),
+            &[ctx.bumps.user_share_pda],
+        ];
+        token::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    from: ctx.accounts.user_share_token.to_account_info(),
+                    authority: ctx.accounts.user_share_pda.to_account_info(),
+                },
+                &[seeds],
+            ),
+            shares,
+        )?;
+
+        let request_id = vault.enqueue_withdrawal(assets)?;
+        vault.release_tier_shares(&user_info.lock_tier, shares)?;
+        user_info.shares = checked_sub(user_info.shares, shares)?;
+        vault.total_shares = checked_sub(vault.total_shares, shares)?;
+
+        let request = &mut ctx.accounts.withdrawal_request;
+        request.vault = vault.key();
+        request.id = request_id;
+        request.owner = ctx.accounts.user.key();
+        request.nft_mint = ctx.accounts.user_nft_mint.key();
+        request.shares = shares;
+        request.assets = assets;
+        request.requested_at = now;
+        request.status = WithdrawalStatus::Pending;
+        request.bump = ctx.bumps.withdrawal_request;
+
+        emit!(WithdrawalRequested {
+            vault: vault.key(),
+            request_id,
+            nft_mint: request.nft_mint,
+            owner: request.owner,
+            shares,
+            assets,
+            share_price: price_per_share(checked_sub(total_assets, assets)?, vault.total_shares)?,
+            seq: vault.next_event_seq()?,
+        });
+
         Ok(())
     }
-}
 
-pub fn calculate_extension(
-    new_deposit: u64,
-    existing_deposit: u64,
-    time_remaining: i64,
-    full_duration: i64,
-) -> i64 {
-    let deposit_ratio = ((new_deposit as u128 * SCALE_U128) / existing_deposit as u128) as u64;
+    pub fn cancel_withdrawal(ctx: Context<CancelWithdrawal>) -> Result<()> {
+        let request = &mut ctx.accounts.withdrawal_request;
+        require!(
+            request.status == WithdrawalStatus::Pending,
+            ErrorCode::RequestNotPending
+        );
+
+        // The reserved assets go back into the vault as shares at today's price
+        let vault = &mut ctx.accounts.vault;
+        let idle_assets = ctx.accounts.vault_token_account.amount;
+        charge_fees(vault, idle_assets, Clock::get()?.unix_timestamp)?;
+        let total_assets = get_total_assets(idle_assets, vault)?;
+        let shares = calculate_shares_to_mint(request.assets, vault.total_shares, total_assets)?;
+        vault.dequeue_assets(request.assets)?;
+
+        // The shares return to the position's tier, within its cap
+        let user_info = &mut ctx.accounts.user_info;
+        let total_shares_after = checked_add(vault.total_shares, shares)?;
+        vault.move_tier_shares(
+            &LockTier::Unlocked,
+            &user_info.lock_tier,
+            shares,
+            checked_add(total_assets, request.assets)?,
+            total_shares_after,
+        )?;
+
+        let seeds: &[&[u8]] = &[
+            VAULT_SEED,
+            vault.asset_mint.as_ref(),
+            vault.owner.as_ref(),
+            &[vault.bump],
+        ];
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    to: ctx.accounts.user_share_token.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                &[seeds],
+            ),
+            shares,
+        )?;
+
+        user_info.shares = checked_add(user_info.shares, shares)?;
+        vault.total_shares = checked_add(vault.total_shares, shares)?;
+        request.status = WithdrawalStatus::Cancelled;
 
-    let time_ratio = ((time_remaining as u128 * SCALE_U128) / full_duration as u128) as u64;
+        emit!(WithdrawalCancelled {
+            vault: vault.key(),
+            request_id: request.id,
+            nft_mint: request.nft_mint,
+            shares,
+            assets: request.assets,
+            share_price: price_per_share(
+                checked_add(total_assets, request.assets)?,
+                vault.total_shares,
+            )?,
+            seq: vault.next_event_seq()?,
+        });
 
-    if time_ratio == 0 {
-        let extension_ratio =
-            (deposit_ratio as u128 * MIN_EXTENSION_RATIO as u128 / SCALE_U128) as u64;
-        return ((full_duration as u128 * extension_ratio as u128) / SCALE_U128) as i64;
+        Ok(())
     }
 
-    let sqrt_ratio = integer_sqrt((time_ratio as u128 * SCALE_U128) as u64);
+    /// Permissionless crank: settles the request at the head of the queue.
+    /// Cancelled requests are skipped, pending ones are paid the assets they
+    /// were priced at once the vault holds enough idle assets. Payment goes to
+    /// the requester's associated token account, which the crank recreates if
+    /// it was closed so one request cannot stall the queue. A request whose
+    /// NFT is bridged out waits at the head until the NFT comes back.
+    pub fn process_queue(ctx: Context<ProcessQueue>) -> Result<()> {
+        let request = &ctx.accounts.withdrawal_request;
+
+        if request.status == WithdrawalStatus::Cancelled {
+            let vault = &mut ctx.accounts.vault;
+            vault.queue_head = checked_add(vault.queue_head, 1)?;
+
+            emit!(WithdrawalSkipped {
+                vault: vault.key(),
+                request_id: request.id,
+                nft_mint: request.nft_mint,
+                queue_head: vault.queue_head,
+                seq: vault.next_event_seq()?,
+            });
+            return Ok(());
+        }
 
-    let linear_part = 600_000 + ((400_000u128 * time_ratio as u128) / SCALE_U128) as u64;
-    let time_factor = ((sqrt_ratio as u128 * linear_part as u128) / SCALE_U128) as u64;
+        let assets = request.assets;
+        let idle_assets = ctx.accounts.vault_token_account.amount;
+        require!(idle_assets >= assets, ErrorCode::InsufficientLiquidity);
+        // Paying a request moves idle and queued assets together
+        let share_price = price_per_share(
+            get_total_assets(idle_assets, &ctx.accounts.vault)?,
+            ctx.accounts.vault.total_shares,
+        )?;
+        ctx.accounts
+            .vault
+            .record_withdrawal(assets, Clock::get()?.unix_timestamp)?;
 
-    let extension_ratio = ((deposit_ratio as u128 * time_factor as u128) / SCALE_U128) as u64;
+        let vault = &ctx.accounts.vault;
+        let asset_mint_key = ctx.accounts.asset_mint.key();
+        let vault_seeds: &[&[u8]] = &[
+            VAULT_SEED,
+            asset_mint_key.as_ref(),
+            vault.owner.as_ref(),
+            &[vault.bump],
+        ];
+        let vault_signer = &[vault_seeds];
 
-    let floored = extension_ratio.max(MIN_EXTENSION_RATIO);
-    let capped = floored.min(MAX_EXTENSION_RATIO);
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault_token_account.to_account_info(),
+                    to: ctx.accounts.recipient.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                vault_signer,
+            ),
+            assets,
+        )?;
 
-    ((full_duration as u128 * capped as u128) / SCALE_U128) as i64
-}
+        let vault = &mut ctx.accounts.vault;
+        vault.dequeue_assets(assets)?;
+        vault.queue_head = checked_add(vault.queue_head, 1)?;
+
+        emit!(WithdrawalFulfilled {
+            vault: vault.key(),
+            request_id: request.id,
+            nft_mint: request.nft_mint,
+            recipient: ctx.accounts.recipient.key(),
+            shares: request.shares,
+            amount: assets,
+            share_price,
+            seq: vault.next_event_seq()?,
+        });
 
-pub fn get_deposit_fee_bps(lock_tier: &LockTier) -> u64 {
-    match lock_tier {
-        LockTier::Unlocked => 50,
-        LockTier::Short => 30,
-        LockTier::Long => 20,
-        LockTier::VeryLong => 10,
+        Ok(())
     }
-}
 
-fn integer_sqrt(n: u64) -> u64 {
-    if n == 0 {
-        return 0;
-    }
-    if n < 4 {
-        return 1;
+    pub fn set_compliance(
+        ctx: Context<SetCompliance>,
+        compliance_authority: Pubkey,
+        allowlist_enabled: bool,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.compliance_authority = compliance_authority;
+        vault.allowlist_enabled = allowlist_enabled;
+
+        emit!(ComplianceUpdated {
+            vault: vault.key(),
+            compliance_authority,
+            allowlist_enabled,
+            seq: vault.next_event_seq()?,
+        });
+        Ok(())
     }
 
-    let mut x = n / 2;
-    let mut y = (x + n / x) / 2;
+    pub fn add_to_allowlist(ctx: Context<AddToAllowlist>, wallet: Pubkey) -> Result<()> {
+        let entry = &mut ctx.accounts.allowlist_entry;
+        entry.vault = ctx.accounts.vault.key();
+        entry.wallet = wallet;
+        entry.added_at = Clock::get()?.unix_timestamp;
+        entry.bump = ctx.bumps.allowlist_entry;
+
+        emit!(AllowlistUpdated {
+            vault: entry.vault,
+            wallet,
+            allowed: true,
+            seq: ctx.accounts.vault.next_event_seq()?,
+        });
+        Ok(())
+    }
 
-    while y < x {
-        x = y;
-        y = (x + n / x) / 2;
+    /// De-listing only blocks new deposits; existing positions can still exit.
+    pub fn remove_from_allowlist(ctx: Context<RemoveFromAllowlist>) -> Result<()> {
+        emit!(AllowlistUpdated {
+            vault: ctx.accounts.vault.key(),
+            wallet: ctx.accounts.allowlist_entry.wallet,
+            allowed: false,
+            seq: ctx.accounts.vault.next_event_seq()?,
+        });
+        Ok(())
     }
-    x
-}
 
-#[account]
-pub struct Vault {
-    pub owner: Pubkey,
-    pub asset_mint: Pubkey,
-    pub share_mint: Pubkey,
-    pub nft_collection_address: Pubkey,
-    //
-    pub last_update_time: i64,
-    pub reserve_factor: u64,
-    pub total_reserves: u64,
-    pub total_shares: u64,
-    pub total_locked_shares: u64,
-    pub total_unlocked_shares: u64,
-    //
-    pub bump: u8,
-}
+    pub fn set_limits(ctx: Context<SetLimits>, limits: VaultLimits) -> Result<()> {
+        require!(limits.epoch_duration >= 0, ErrorCode::InvalidAmount);
+        let vault = &mut ctx.accounts.vault;
+        vault.limits = limits;
 
-fn get_total_assets(token_balance: u64, _vault: &Vault) -> Result<u64> {
-    Ok(token_balance)
-}
+        emit!(LimitsUpdated {
+            vault: vault.key(),
+            limits,
+            seq: vault.next_event_seq()?,
+        });
+        Ok(())
+    }
 
-#[derive(Accounts)]
+    pub fn set_fees(
+        ctx: Context<SetFees>,
+        management_fee_bps: u64,
+        performance_fee_bps: u64,
+        fee_recipient: Pubkey,
+    ) -> Result<()> {
+        require!(
+            management_fee_bps <= MAX_MANAGEMENT_FEE_BPS,
+            ErrorCode::FeeTooHigh
+        );
+        require!(
+            performance_fee_bps <= MAX_PERFORMANCE_FEE_BPS,
+            ErrorCode::FeeTooHigh
+        );
+
+        // Time so far is charged at the old rates
+        let vault = &mut ctx.accounts.vault;
+        charge_fees(
+            vault,
+            ctx.accounts.vault_token_account.amount,
+            Clock::get()?.unix_timestamp,
+        )?;
+        require!(
+            fee_recipient == vault.fee_recipient || vault.pending_fee_shares == 0,
+            ErrorCode::FeesNotClaimed
+        );
+
+        vault.management_fee_bps = management_fee_bps;
+        vault.performance_fee_bps = performance_fee_bps;
+        vault.fee_recipient = fee_recipient;
+
+        emit!(FeesUpdated {
+            vault: vault.key(),
+            management_fee_bps,
+            performance_fee_bps,
+            fee_recipient,
+            seq: vault.next_event_seq()?,
+        });
+        Ok(())
+    }
+
+    /// Permissionless crank: charges management and performance fees since
+    /// the last charge and mints every fee share owed so far to the fee
+    /// recipient.
+    pub fn accrue_fees(ctx: Context<AccrueFees>) -> Result<()> {
+        charge_fees(
+            &mut ctx.accounts.vault,
+            ctx.accounts.vault_token_account.amount,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        let vault = &ctx.accounts.vault;
+        let fee_shares = vault.pending_fee_shares;
+        if fee_shares > 0 {
+            let asset_mint_key = ctx.accounts.asset_mint.key();
+            let seeds: &[&[u8]] = &[
+                VAULT_SEED,
+                asset_mint_key.as_ref(),
+                vault.owner.as_ref(),
+                &[vault.bump],
+            ];
+            let signer = &[seeds];
+
+            token::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    MintTo {
+                        mint: ctx.accounts.share_mint.to_account_info(),
+                        to: ctx.accounts.fee_recipient_share_token.to_account_info(),
+                        authority: ctx.accounts.vault.to_account_info(),
+                    },
+                    signer,
+                ),
+                fee_shares,
+            )?;
+            let vault = &mut ctx.accounts.vault;
+            vault.pending_fee_shares = 0;
+
+            // Fee shares were already counted in `total_shares` when charged
+            emit!(FeeSharesMinted {
+                vault: vault.key(),
+                fee_recipient: vault.fee_recipient,
+                shares: fee_shares,
+                share_price: price_per_share(
+                    get_total_assets(ctx.accounts.vault_token_account.amount, vault)?,
+                    vault.total_shares,
+                )?,
+                seq: vault.next_event_seq()?,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn set_risk_manager(ctx: Context<SetRiskManager>, risk_manager: Pubkey) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.risk_manager = risk_manager;
+
+        emit!(RiskManagerUpdated {
+            vault: vault.key(),
+            risk_manager,
+            seq: vault.next_event_seq()?,
+        });
+        Ok(())
+    }
+
+    pub fn add_strategy(
+        ctx: Context<AddStrategy>,
+        strategy_state: Pubkey,
+        strategy_token_account: Pubkey,
+        debt_ceiling: u64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        require!(
+            vault.strategies.len() < MAX_STRATEGIES,
+            ErrorCode::StrategyLimitReached
+        );
+
+        let strategy = &mut ctx.accounts.strategy;
+        strategy.vault = vault.key();
+        strategy.strategy_program = ctx.accounts.strategy_program.key();
+        strategy.strategy_state = strategy_state;
+        strategy.strategy_token_account = strategy_token_account;
+        strategy.debt_ceiling = debt_ceiling;
+        strategy.current_debt = 0;
+        strategy.last_report = Clock::get()?.unix_timestamp;
+        strategy.bump = ctx.bumps.strategy;
+
+        vault.strategies.push(strategy.key());
+
+        emit!(StrategyAdded {
+            vault: vault.key(),
+            strategy: strategy.key(),
+            strategy_program: strategy.strategy_program,
+            debt_ceiling,
+            seq: vault.next_event_seq()?,
+        });
+        Ok(())
+    }
+
+    pub fn set_strategy_debt_ceiling(
+        ctx: Context<UpdateStrategy>,
+        debt_ceiling: u64,
+    ) -> Result<()> {
+        ctx.accounts.strategy.debt_ceiling = debt_ceiling;
+
+        emit!(StrategyDebtCeilingUpdated {
+            vault: ctx.accounts.vault.key(),
+            strategy: ctx.accounts.strategy.key(),
+            debt_ceiling,
+            seq: ctx.accounts.vault.next_event_seq()?,
+        });
+        Ok(())
+    }
+
+    pub fn remove_strategy(ctx: Context<RemoveStrategy>) -> Result<()> {
+        require!(
+            ctx.accounts.strategy.current_debt == 0,
+            ErrorCode::StrategyHasDebt
+        );
+        let strategy_key = ctx.accounts.strategy.key();
+        let vault = &mut ctx.accounts.vault;
+        vault.strategies.retain(|key| *key != strategy_key);
+
+        emit!(StrategyRemoved {
+            vault: vault.key(),
+            strategy: strategy_key,
+            seq: vault.next_event_seq()?,
+        });
+        Ok(())
+    }
+
+    pub fn allocate(ctx: Context<StrategyAllocation>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let vault = &ctx.accounts.vault;
+        let strategy = &ctx.accounts.strategy;
+
+        let new_debt = checked_add(strategy.current_debt, amount)?;
+        require!(
+            new_debt <= strategy.debt_ceiling,
+            ErrorCode::DebtCeilingExceeded
+        );
+
+        // Reserves and queued withdrawals stay idle in the vault
+        let balance_before = ctx.accounts.vault_token_account.amount;
+        vault.check_liquidity(balance_before.saturating_sub(vault.total_reserves), amount)?;
+
+        let asset_mint_key = ctx.accounts.asset_mint.key();
+        let seeds: &[&[u8]] = &[
+            VAULT_SEED,
+            asset_mint_key.as_ref(),
+            vault.owner.as_ref(),
+            &[vault.bump],
+        ];
+        let signer = &[seeds];
+
+        strategy_deposit(
+            CpiContext::new_with_signer(
+                ctx.accounts.strategy_program.to_account_info(),
+                StrategyTransfer {
+                    vault: ctx.accounts.vault.to_account_info(),
+                    vault_token_account: ctx.accounts.vault_token_account.to_account_info(),
+                    strategy_state: ctx.accounts.strategy_state.to_account_info(),
+                    strategy_token_account: ctx.accounts.strategy_token_account.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+
+        ctx.accounts.vault_token_account.reload()?;
+        let sent = checked_sub(balance_before, ctx.accounts.vault_token_account.amount)?;
+
+        let strategy = &mut ctx.accounts.strategy;
+        strategy.current_debt = checked_add(strategy.current_debt, sent)?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.total_debt = checked_add(vault.total_debt, sent)?;
+
+        emit!(StrategyAllocated {
+            vault: vault.key(),
+            strategy: strategy.key(),
+            amount: sent,
+            current_debt: strategy.current_debt,
+            seq: vault.next_event_seq()?,
+        });
+
+        Ok(())
+    }
+
+    pub fn deallocate(ctx: Context<StrategyAllocation>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            ctx.accounts.strategy.current_debt >= amount,
+            ErrorCode::InsufficientStrategyDebt
+        );
+
+        let vault = &ctx.accounts.vault;
+        let balance_before = ctx.accounts.vault_token_account.amount;
+
+        let asset_mint_key = ctx.accounts.asset_mint.key();
+        let seeds: &[&[u8]] = &[
+            VAULT_SEED,
+            asset_mint_key.as_ref(),
+            vault.owner.as_ref(),
+            &[vault.bump],
+        ];
+        let signer = &[seeds];
+
+        strategy_withdraw(
+            CpiContext::new_with_signer(
+                ctx.accounts.strategy_program.to_account_info(),
+                StrategyTransfer {
+                    vault: ctx.accounts.vault.to_account_info(),
+                    vault_token_account: ctx.accounts.vault_token_account.to_account_info(),
+                    strategy_state: ctx.accounts.strategy_state.to_account_info(),
+                    strategy_token_account: ctx.accounts.strategy_token_account.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+
+        ctx.accounts.vault_token_account.reload()?;
+        let received =
+            calculate_received_amount(balance_before, ctx.accounts.vault_token_account.amount)?;
+
+        // Debt is written down by what was asked for; any shortfall is a loss,
+        // visible in the event as `amount - received`.
+        let strategy = &mut ctx.accounts.strategy;
+        strategy.current_debt = checked_sub(strategy.current_debt, amount)?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.total_debt = checked_sub(vault.total_debt, amount)?;
+
+        emit!(StrategyDeallocated {
+            vault: vault.key(),
+            strategy: strategy.key(),
+            amount,
+            received,
+            current_debt: strategy.current_debt,
+            seq: vault.next_event_seq()?,
+        });
+
+        Ok(())
+    }
+
+    /// Harvest for the risk manager or vault owner: marks the strategy
+    /// position to the value the strategy program reports, booking the
+    /// difference as gain or loss.
+    pub fn report(ctx: Context<StrategyReport>) -> Result<()> {
+        let reported_value = strategy_total_assets(CpiContext::new(
+            ctx.accounts.strategy_program.to_account_info(),
+            StrategyValue {
+                strategy_state: ctx.accounts.strategy_state.to_account_info(),
+                strategy_token_account: ctx.accounts.strategy_token_account.to_account_info(),
+            },
+        ))?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let strategy = &mut ctx.accounts.strategy;
+        let vault = &mut ctx.accounts.vault;
+        // Fees up to now are charged on the value before the gain or loss
+        charge_fees(vault, ctx.accounts.vault_token_account.amount, now)?;
+        let (gain, loss) = calculate_strategy_pnl(strategy.current_debt, reported_value);
+
+        vault.total_debt = vault
+            .total_debt
+            .checked_sub(strategy.current_debt)
+            .and_then(|debt| debt.checked_add(reported_value))
+            .ok_or(ErrorCode::MathOverflow)?;
+        strategy.current_debt = reported_value;
+        strategy.last_report = now;
+
+        emit!(StrategyReported {
+            vault: vault.key(),
+            strategy: strategy.key(),
+            gain,
+            loss,
+            current_debt: strategy.current_debt,
+            seq: vault.next_event_seq()?,
+        });
+
+        Ok(())
+    }
+
+    /// Records (or replaces) `operator`'s approval on the signer's position.
+    /// The approval lapses at `expiry` or as soon as the NFT changes hands.
+    pub fn approve_operator(
+        ctx: Context<ApproveOperator>,
+        operator: Pubkey,
+        scopes: u8,
+        expiry: i64,
+    ) -> Result<()> {
+        require!(
+            scopes != 0 && scopes & !SCOPE_ALL == 0,
+            ErrorCode::InvalidOperatorScope
+        );
+        require!(
+            expiry > Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidOperatorExpiry
+        );
+        require_keys_neq!(
+            operator,
+            ctx.accounts.holder.key(),
+            ErrorCode::UnauthorizedOperator
+        );
+
+        let approval = &mut ctx.accounts.operator_approval;
+        approval.vault = ctx.accounts.vault.key();
+        approval.nft_mint = ctx.accounts.user_nft_mint.key();
+        approval.approver = ctx.accounts.holder.key();
+        approval.operator = operator;
+        approval.scopes = scopes;
+        approval.expiry = expiry;
+        approval.bump = ctx.bumps.operator_approval;
+
+        emit!(OperatorApproved {
+            vault: approval.vault,
+            nft_mint: approval.nft_mint,
+            approver: approval.approver,
+            operator,
+            scopes,
+            expiry,
+            seq: ctx.accounts.vault.next_event_seq()?,
+        });
+        Ok(())
+    }
+
+    pub fn revoke_operator(ctx: Context<RevokeOperator>) -> Result<()> {
+        let approval = &ctx.accounts.operator_approval;
+        emit!(OperatorRevoked {
+            vault: ctx.accounts.vault.key(),
+            nft_mint: approval.nft_mint,
+            approver: approval.approver,
+            operator: approval.operator,
+            seq: ctx.accounts.vault.next_event_seq()?,
+        });
+        Ok(())
+    }
+
+    /// Re-commits an existing position to `tier` for the tier's full duration
+    /// without adding funds. A running lock can only be moved to the same or
+    /// a longer tier and is never shortened.
+    pub fn relock(ctx: Context<Relock>, tier: u8) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        authorize_position(
+            &ctx.accounts.user.key(),
+            &ctx.accounts.user_nft_token.owner,
+            ctx.accounts.operator_approval.as_deref(),
+            SCOPE_RELOCK,
+            now,
+        )?;
+
+        let locktier = LockTier::try_from(tier)?;
+        require!(locktier != LockTier::Unlocked, ErrorCode::InvalidLockTier);
+
+        let vault = &mut ctx.accounts.vault;
+        let idle_assets = ctx.accounts.vault_token_account.amount;
+        charge_fees(vault, idle_assets, now)?;
+        let user_info = &mut ctx.accounts.user_info;
+        require!(user_info.shares > 0, ErrorCode::InsufficientShares);
+
+        let still_locked = user_info.locked_until > now;
+        if still_locked {
+            require!(
+                locktier as u8 >= user_info.lock_tier as u8,
+                ErrorCode::CannotShortenLock
+            );
+        }
+
+        if locktier != user_info.lock_tier {
+            let total_assets = get_total_assets(idle_assets, vault)?;
+            let total_shares = vault.total_shares;
+            vault.move_tier_shares(
+                &user_info.lock_tier,
+                &locktier,
+                user_info.shares,
+                total_assets,
+                total_shares,
+            )?;
+            user_info.lock_tier = locktier;
+        }
+
+        user_info.deposit_time = now;
+        user_info.locked_until = user_info
+            .locked_until
+            .max(checked_add_i64(now, get_lock_duration(&locktier))?);
+
+        emit!(PositionRelocked {
+            vault: vault.key(),
+            nft_mint: ctx.accounts.user_nft_mint.key(),
+            caller: ctx.accounts.user.key(),
+            tier,
+            locked_until: user_info.locked_until,
+            seq: vault.next_event_seq()?,
+        });
+        Ok(())
+    }
+
+    /// Withdraws the position's gain above its cost basis. Proceeds always go
+    /// to the current NFT holder's asset account, whoever signs.
+    pub fn harvest(ctx: Context<Harvest>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        authorize_position(
+            &ctx.accounts.user.key(),
+            &ctx.accounts.holder.key(),
+            ctx.accounts.operator_approval.as_deref(),
+            SCOPE_HARVEST,
+            now,
+        )?;
+
+        let user_info = &mut ctx.accounts.user_info;
+        require!(now >= user_info.locked_until, ErrorCode::StillLocked);
+
+        let vault = &mut ctx.accounts.vault;
+        let idle_assets = ctx.accounts.vault_token_account.amount;
+        charge_fees(vault, idle_assets, now)?;
+        let total_assets = get_total_assets(idle_assets, vault)?;
+
+        let shares = calculate_harvestable_shares(
+            user_info.shares,
+            user_info.deposit_amount,
+            total_assets,
+            vault.total_shares,
+        )?;
+        require!(shares > 0, ErrorCode::NothingToHarvest);
+
+        let assets_to_withdraw =
+            calculate_assets_for_shares(shares, total_assets, vault.total_shares)?;
+        vault.check_liquidity(idle_assets, assets_to_withdraw)?;
+        vault.record_withdrawal(assets_to_withdraw, now)?;
+
+        let user_nft_mint_key = ctx.accounts.user_nft_mint.key();
+        let seeds: &[&[u8]] = &[
+            USER_SHARES_SEED,
+            user_nft_mint_key.as_ref(),
+            &[ctx.bumps.user_share_pda],
+        ];
+        token::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    from: ctx.accounts.user_share_token.to_account_info(),
+                    authority: ctx.accounts.user_share_pda.to_account_info(),
+                },
+                &[seeds],
+            ),
+            shares,
+        )?;
+
+        vault.release_tier_shares(&user_info.lock_tier, shares)?;
+        user_info.shares = checked_sub(user_info.shares, shares)?;
+        vault.total_shares = checked_sub(vault.total_shares, shares)?;
+
+        let asset_mint_key = ctx.accounts.asset_mint.key();
+        let vault_seeds: &[&[u8]] = &[
+            VAULT_SEED,
+            asset_mint_key.as_ref(),
+            vault.owner.as_ref(),
+            &[vault.bump],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault_token_account.to_account_info(),
+                    to: ctx.accounts.holder_asset_token.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                &[vault_seeds],
+            ),
+            assets_to_withdraw,
+        )?;
+
+        emit!(Harvested {
+            vault: vault.key(),
+            nft_mint: user_nft_mint_key,
+            caller: ctx.accounts.user.key(),
+            holder: ctx.accounts.holder.key(),
+            shares,
+            amount: assets_to_withdraw,
+            share_price: price_per_share(
+                checked_sub(total_assets, assets_to_withdraw)?,
+                vault.total_shares,
+            )?,
+            seq: vault.next_event_seq()?,
+        });
+        Ok(())
+    }
+
+    /// Snapshot of the position behind `user_nft_mint`, priced at the vault's
+    /// share price once accrued fees are charged. Simulate it (`.view()`) to
+    /// feed the `base_uri` renderer; the NFT's Metaplex metadata is never
+    /// rewritten.
+    pub fn position_metadata(ctx: Context<PositionMetadataView>) -> Result<PositionMetadata> {
+        let now = Clock::get()?.unix_timestamp;
+        let idle_assets = ctx.accounts.vault_token_account.amount;
+        charge_fees(&mut ctx.accounts.vault, idle_assets, now)?;
+        PositionMetadata::new(
+            ctx.accounts.vault.key(),
+            &ctx.accounts.vault,
+            &ctx.accounts.user_info,
+            idle_assets,
+            now,
+        )
+    }
+
+    /// Sends the NFT to `target_chain_id` through unique_low with a snapshot of
+    /// its position in the same message. The shares and lock terms wait in a
+    /// `PositionEscrow` and the `UserInfo` is closed until the NFT comes back.
+    pub fn bridge_position(
+        ctx: Context<BridgePosition>,
+        nonce: u32,
+        target_chain_id: u16,
+        recipient: [u8; 32],
+    ) -> Result<()> {
+        let user_info = &ctx.accounts.user_info;
+        require!(user_info.shares > 0, ErrorCode::InsufficientShares);
+        let now = Clock::get()?.unix_timestamp;
+        let idle_assets = ctx.accounts.vault_token_account.amount;
+        charge_fees(&mut ctx.accounts.vault, idle_assets, now)?;
+        let vault_key = ctx.accounts.vault.key();
+        let snapshot =
+            PositionMetadata::new(vault_key, &ctx.accounts.vault, user_info, idle_assets, now)?
+                .snapshot();
+
+        ctx.accounts
+            .position_escrow
+            .set_inner(PositionEscrow::new(user_info, now, ctx.bumps.position_escrow));
+
+        let user_nft_mint_key = ctx.accounts.user_nft_mint.key();
+        let seeds: &[&[u8]] = &[
+            USER_SHARES_SEED,
+            user_nft_mint_key.as_ref(),
+            &[ctx.bumps.user_share_pda],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_share_token.to_account_info(),
+                    to: ctx.accounts.escrow_share_token.to_account_info(),
+                    authority: ctx.accounts.user_share_pda.to_account_info(),
+                },
+                &[seeds],
+            ),
+            snapshot.shares,
+        )?;
+
+        let authority_seeds: &[&[u8]] = &[POSITION_AUTHORITY_SEED, &[ctx.bumps.position_authority]];
+        unique_low::cpi::request_cross_chain_transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.nft_program.to_account_info(),
+                unique_low::cpi::accounts::RequestCrossChainTransfer {
+                    request: unique_low::cpi::accounts::RequestCrossChainMint {
+                        collection: ctx.accounts.nft_collection.to_account_info(),
+                        foreign_emitter: ctx.accounts.nft_foreign_emitter.to_account_info(),
+                        mint: ctx.accounts.user_nft_mint.to_account_info(),
+                        mint_record: ctx.accounts.nft_mint_record.to_account_info(),
+                        unique_id_record: ctx.accounts.nft_unique_id_record.to_account_info(),
+                        token_account: ctx.accounts.user_nft_token.to_account_info(),
+                        escrow_token_account: ctx.accounts.nft_escrow.to_account_info(),
+                        user: ctx.accounts.user.to_account_info(),
+                        wormhole_emitter: ctx.accounts.wormhole_emitter.to_account_info(),
+                        wormhole_sequence: ctx.accounts.wormhole_sequence.to_account_info(),
+                        wormhole_message: ctx.accounts.wormhole_message.to_account_info(),
+                        wormhole_bridge: ctx.accounts.wormhole_bridge.to_account_info(),
+                        wormhole_fee_collector: ctx
+                            .accounts
+                            .wormhole_fee_collector
+                            .to_account_info(),
+                        wormhole_program: ctx.accounts.wormhole_program.to_account_info(),
+                        token_program: ctx.accounts.token_program.to_account_info(),
+                        associated_token_program: ctx
+                            .accounts
+                            .associated_token_program
+                            .to_account_info(),
+                        clock: ctx.accounts.clock.to_account_info(),
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                        rent: ctx.accounts.rent.to_account_info(),
+                    },
+                    position_authority: ctx.accounts.position_authority.to_account_info(),
+                },
+                &[authority_seeds],
+            ),
+            nonce,
+            target_chain_id,
+            recipient,
+            snapshot.clone(),
+        )?;
+
+        emit!(PositionBridged {
+            vault: vault_key,
+            nft_mint: user_nft_mint_key,
+            shares: snapshot.shares,
+            assets: snapshot.assets,
+            target_chain_id,
+            seq: ctx.accounts.vault.next_event_seq()?,
+        });
+        Ok(())
+    }
+
+    /// Hands an escrowed position back to its NFT when a VAA from the
+    /// registered emitter returns it with the same shares. The `UserInfo` is
+    /// recreated, or merged into if the NFT was funded again meanwhile. Only a
+    /// later `locked_until` is taken from the message.
+    pub fn receive_position(ctx: Context<ReceivePosition>, _vaa_hash: [u8; 32]) -> Result<()> {
+        let (vaa, message) = unique_low::verify_inbound_vaa(
+            &ctx.accounts.posted_vaa,
+            &ctx.accounts.foreign_emitter,
+        )?;
+        let vault_key = ctx.accounts.vault.key();
+        let escrow = &ctx.accounts.position_escrow;
+        let Message::PositionTransfer(transfer) = message else {
+            return err!(ErrorCode::InvalidPositionPayload);
+        };
+        let position = transfer.position;
+        require!(
+            position.vault == vault_key && position.shares == escrow.shares,
+            ErrorCode::InvalidPositionPayload
+        );
+        let returning = escrow.restore(position.locked_until);
+
+        ctx.accounts.claimed_vaa.set_inner(ClaimedVaa {
+            emitter_chain: vaa.emitter_chain,
+            sequence: vaa.sequence,
+            bump: ctx.bumps.claimed_vaa,
+        });
+
+        release_escrowed_shares(
+            escrow,
+            ctx.accounts.escrow_share_token.to_account_info(),
+            ctx.accounts.user_share_token.to_account_info(),
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+        )?;
+        let user_info = &mut ctx.accounts.user_info;
+        restore_position(
+            &mut ctx.accounts.vault,
+            user_info,
+            &returning,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        emit!(PositionReturned {
+            vault: vault_key,
+            nft_mint: ctx.accounts.user_nft_mint.key(),
+            shares: returning.shares,
+            locked_until: user_info.locked_until,
+            emitter_chain: vaa.emitter_chain,
+            sequence: vaa.sequence,
+            seq: ctx.accounts.vault.next_event_seq()?,
+        });
+        Ok(())
+    }
+
+    /// Lets the holder take back an escrowed position whose NFT came home on
+    /// a plain mint VAA instead of the position transfer that
+    /// `receive_position` redeems. The VAA must be one unique_low already
+    /// released the NFT to the caller on, emitted after the position left.
+    /// The lock terms are the ones it left with.
+    pub fn reclaim_position(ctx: Context<ReclaimPosition>, _vaa_hash: [u8; 32]) -> Result<()> {
+        let (vaa, _) = unique_low::verify_release_vaa(
+            &ctx.accounts.posted_vaa,
+            &ctx.accounts.foreign_emitter,
+            &ctx.accounts.user.key(),
+        )?;
+        // A position transfer carries its own terms for `receive_position`
+        require!(
+            matches!(vaa.message()?, Message::MintRequest(_)),
+            ErrorCode::InvalidPositionPayload
+        );
+        let escrow = &ctx.accounts.position_escrow;
+        require!(
+            i64::from(vaa.vaa_time) >= escrow.bridged_at,
+            ErrorCode::InvalidPositionPayload
+        );
+        let returning = escrow.restore(escrow.locked_until);
+
+        ctx.accounts.claimed_vaa.set_inner(ClaimedVaa {
+            emitter_chain: vaa.emitter_chain,
+            sequence: vaa.sequence,
+            bump: ctx.bumps.claimed_vaa,
+        });
+
+        release_escrowed_shares(
+            escrow,
+            ctx.accounts.escrow_share_token.to_account_info(),
+            ctx.accounts.user_share_token.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+        )?;
+        let user_info = &mut ctx.accounts.user_info;
+        restore_position(
+            &mut ctx.accounts.vault,
+            user_info,
+            &returning,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        emit!(PositionReclaimed {
+            vault: ctx.accounts.vault.key(),
+            nft_mint: ctx.accounts.user_nft_mint.key(),
+            holder: ctx.accounts.user.key(),
+            shares: returning.shares,
+            locked_until: user_info.locked_until,
+            seq: ctx.accounts.vault.next_event_seq()?,
+        });
+        Ok(())
+    }
+
+    /// Folds position B (`source`) into position A (`target`). Both NFTs must
+    /// be held by the signer. B's share account and `UserInfo` are closed.
+    pub fn merge_positions(ctx: Context<MergePositions>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let vault = &mut ctx.accounts.vault;
+        let target = &mut ctx.accounts.target_user_info;
+        let source = &ctx.accounts.source_user_info;
+        require!(source.shares > 0, ErrorCode::InsufficientShares);
+
+        let (tier, locked_until) = calculate_merged_lock(target, source, now)?;
+
+        let idle_assets = ctx.accounts.vault_token_account.amount;
+        charge_fees(vault, idle_assets, now)?;
+        let total_assets = get_total_assets(idle_assets, vault)?;
+        let total_shares = vault.total_shares;
+        vault.move_tier_shares(
+            &target.lock_tier,
+            &tier,
+            target.shares,
+            total_assets,
+            total_shares,
+        )?;
+        vault.move_tier_shares(
+            &source.lock_tier,
+            &tier,
+            source.shares,
+            total_assets,
+            total_shares,
+        )?;
+        vault.check_position_size(
+            checked_add(target.shares, source.shares)?,
+            total_assets,
+            total_shares,
+        )?;
+
+        let source_mint_key = ctx.accounts.source_nft_mint.key();
+        let seeds: &[&[u8]] = &[
+            USER_SHARES_SEED,
+            source_mint_key.as_ref(),
+            &[ctx.bumps.source_share_pda],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.source_share_token.to_account_info(),
+                    to: ctx.accounts.target_share_token.to_account_info(),
+                    authority: ctx.accounts.source_share_pda.to_account_info(),
+                },
+                &[seeds],
+            ),
+            source.shares,
+        )?;
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.source_share_token.to_account_info(),
+                destination: ctx.accounts.user.to_account_info(),
+                authority: ctx.accounts.source_share_pda.to_account_info(),
+            },
+            &[seeds],
+        ))?;
+
+        target.shares = checked_add(target.shares, source.shares)?;
+        target.deposit_amount = checked_add(target.deposit_amount, source.deposit_amount)?;
+        target.lock_tier = tier;
+        target.locked_until = locked_until;
+
+        emit!(PositionsMerged {
+            vault: vault.key(),
+            target_nft_mint: ctx.accounts.target_nft_mint.key(),
+            source_nft_mint: source_mint_key,
+            shares: source.shares,
+            locked_until,
+            seq: vault.next_event_seq()?,
+        });
+        Ok(())
+    }
+
+    /// Mints a new `unique_low` NFT to the signer and moves `shares` of the
+    /// position, with a proportional slice of its cost basis, onto it. The new
+    /// position inherits the source's tier and lock expiry.
+    pub fn split_position(ctx: Context<SplitPosition>, shares: u64) -> Result<()> {
+        let source = &mut ctx.accounts.source_user_info;
+        require!(
+            shares > 0 && shares < source.shares,
+            ErrorCode::InvalidAmount
+        );
+        let deposit_moved = mul_div(source.deposit_amount, shares, source.shares, Rounding::Down)?;
+        charge_fees(
+            &mut ctx.accounts.vault,
+            ctx.accounts.vault_token_account.amount,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        unique_low::cpi::mint_nft_with_metadata(CpiContext::new(
+            ctx.accounts.nft_program.to_account_info(),
+            unique_low::cpi::accounts::MintNftWithMetadata {
+                collection: ctx.accounts.nft_collection.to_account_info(),
+                user_state: ctx.accounts.nft_user_state.to_account_info(),
+                unique_id_record: ctx.accounts.new_unique_id_record.to_account_info(),
+                token_id_record: ctx.accounts.new_token_id_record.to_account_info(),
+                mint_record: ctx.accounts.new_mint_record.to_account_info(),
+                mint: ctx.accounts.new_nft_mint.to_account_info(),
+                token_account: ctx.accounts.new_nft_token.to_account_info(),
+                metadata: ctx.accounts.new_nft_metadata.to_account_info(),
+                master_edition: ctx.accounts.new_nft_master_edition.to_account_info(),
+                collection_mint: ctx.accounts.nft_collection_mint.to_account_info(),
+                collection_metadata: ctx.accounts.nft_collection_metadata.to_account_info(),
+                collection_master_edition: ctx
+                    .accounts
+                    .nft_collection_master_edition
+                    .to_account_info(),
+                user: ctx.accounts.user.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+                metadata_program: ctx.accounts.metadata_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+            },
+        ))?;
+
+        let source_mint_key = ctx.accounts.source_nft_mint.key();
+        let seeds: &[&[u8]] = &[
+            USER_SHARES_SEED,
+            source_mint_key.as_ref(),
+            &[ctx.bumps.source_share_pda],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.source_share_token.to_account_info(),
+                    to: ctx.accounts.new_share_token.to_account_info(),
+                    authority: ctx.accounts.source_share_pda.to_account_info(),
+                },
+                &[seeds],
+            ),
+            shares,
+        )?;
+
+        source.shares = checked_sub(source.shares, shares)?;
+        source.deposit_amount = checked_sub(source.deposit_amount, deposit_moved)?;
+
+        let new_position = &mut ctx.accounts.new_user_info;
+        new_position.vault = ctx.accounts.vault.key();
+        new_position.nft_mint = ctx.accounts.new_nft_mint.key();
+        new_position.deposit_amount = deposit_moved;
+        new_position.shares = shares;
+        new_position.lock_tier = source.lock_tier;
+        new_position.locked_until = source.locked_until;
+        new_position.deposit_time = source.deposit_time;
+        new_position.version = USER_INFO_VERSION;
+
+        emit!(PositionSplit {
+            vault: new_position.vault,
+            source_nft_mint: source_mint_key,
+            new_nft_mint: new_position.nft_mint,
+            shares,
+            deposit_amount: deposit_moved,
+            seq: ctx.accounts.vault.next_event_seq()?,
+        });
+        Ok(())
+    }
+
+    /// Closes an emptied position and returns the rent of its share account
+    /// and `UserInfo` to the current NFT holder. Shares sent to the share
+    /// account from outside the position are burned rather than blocking the
+    /// close.
+    pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
+        let user_info = &ctx.accounts.user_info;
+        require!(user_info.shares == 0, ErrorCode::PositionNotEmpty);
+        require!(
+            Clock::get()?.unix_timestamp >= user_info.locked_until,
+            ErrorCode::StillLocked
+        );
+
+        let user_nft_mint_key = ctx.accounts.user_nft_mint.key();
+        let seeds: &[&[u8]] = &[
+            USER_SHARES_SEED,
+            user_nft_mint_key.as_ref(),
+            &[ctx.bumps.user_share_pda],
+        ];
+        let stray_shares = ctx.accounts.user_share_token.amount;
+        if stray_shares > 0 {
+            token::burn(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Burn {
+                        mint: ctx.accounts.share_mint.to_account_info(),
+                        from: ctx.accounts.user_share_token.to_account_info(),
+                        authority: ctx.accounts.user_share_pda.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                stray_shares,
+            )?;
+            let vault = &mut ctx.accounts.vault;
+            vault.total_shares = checked_sub(vault.total_shares, stray_shares)?;
+        }
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.user_share_token.to_account_info(),
+                destination: ctx.accounts.user.to_account_info(),
+                authority: ctx.accounts.user_share_pda.to_account_info(),
+            },
+            &[seeds],
+        ))?;
+
+        emit!(PositionClosed {
+            vault: ctx.accounts.vault.key(),
+            nft_mint: user_nft_mint_key,
+            holder: ctx.accounts.user.key(),
+            seq: ctx.accounts.vault.next_event_seq()?,
+        });
+        Ok(())
+    }
+
+    /// Grows a vault created by an older build to the current layout and
+    /// backfills the fields that did not exist when it was created.
+    pub fn migrate_vault(ctx: Context<MigrateVault>) -> Result<()> {
+        let vault_info = ctx.accounts.vault.to_account_info();
+        {
+            let data = vault_info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 + 32 && data[..8] == *Vault::DISCRIMINATOR,
+                ErrorCode::InvalidAccountLayout
+            );
+            require!(
+                data[8..40] == ctx.accounts.owner.key().to_bytes(),
+                ErrorCode::Unauthorized
+            );
+        }
+
+        resize_account(
+            &vault_info,
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            8 + Vault::INIT_SPACE,
+        )?;
+
+        let mut vault = Vault::try_deserialize(&mut &vault_info.try_borrow_data()?[..])?;
+        require!(vault.version < VAULT_VERSION, ErrorCode::AlreadyMigrated);
+        let from_version = vault.version;
+
+        if from_version == 0 {
+            if vault.risk_manager == Pubkey::default() {
+                vault.risk_manager = vault.owner;
+            }
+            if vault.fee_recipient == Pubkey::default() {
+                vault.fee_recipient = vault.owner;
+            }
+            if vault.compliance_authority == Pubkey::default() {
+                vault.compliance_authority = vault.owner;
+            }
+            if vault.high_water_mark == 0 {
+                vault.high_water_mark = PRECISION;
+            }
+        }
+        if from_version < 2 {
+            vault.nft_collection_mint = unique_low::collection_mint_address();
+        }
+        vault.version = VAULT_VERSION;
+        let seq = vault.next_event_seq()?;
+        vault.try_serialize(&mut &mut vault_info.try_borrow_mut_data()?[..])?;
+
+        emit!(AccountMigrated {
+            vault: vault_info.key(),
+            account: vault_info.key(),
+            from_version,
+            to_version: VAULT_VERSION,
+            seq,
+        });
+        Ok(())
+    }
+
+    /// Grows a `UserInfo` created by an older build to the current layout.
+    /// Permissionless: it changes nothing but size and version.
+    pub fn migrate_user_info(ctx: Context<MigrateUserInfo>) -> Result<()> {
+        let info = ctx.accounts.user_info.to_account_info();
+        {
+            let data = info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == *UserInfo::DISCRIMINATOR,
+                ErrorCode::InvalidAccountLayout
+            );
+        }
+
+        resize_account(
+            &info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            8 + UserInfo::INIT_SPACE,
+        )?;
+
+        let mut user_info = UserInfo::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        require!(
+            user_info.version < USER_INFO_VERSION,
+            ErrorCode::AlreadyMigrated
+        );
+        let from_version = user_info.version;
+        user_info.version = USER_INFO_VERSION;
+        user_info.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        emit!(AccountMigrated {
+            vault: ctx.accounts.vault.key(),
+            account: info.key(),
+            from_version,
+            to_version: USER_INFO_VERSION,
+            seq: ctx.accounts.vault.next_event_seq()?,
+        });
+        Ok(())
+    }
+
+    pub fn close_vault(_ctx: Context<CloseVault>) -> Result<()> {
+        // Vault will be automatically closed and lamports returned to authority
+        Ok(())
+    }
+}
+
+pub fn calculate_extension(
+    new_deposit: u64,
+    existing_deposit: u64,
+    time_remaining: i64,
+    full_duration: i64,
+) -> Result<i64> {
+    require!(existing_deposit > 0, ErrorCode::InvalidDepositAmount);
+    if full_duration <= 0 || time_remaining <= 0 {
+        return Ok(0);
+    }
+    let full_duration = to_u64(full_duration)?;
+
+    // Ratios beyond u64 saturate; the extension ratio is capped below anyway
+    let deposit_ratio = mul_div_saturating(new_deposit, SCALE, existing_deposit)?;
+    let time_ratio = mul_div_saturating(to_u64(time_remaining)?, SCALE, full_duration)?;
+
+    let extension_ratio = if time_ratio == 0 {
+        mul_div_saturating(deposit_ratio, MIN_EXTENSION_RATIO, SCALE)?
+    } else {
+        let sqrt_ratio = integer_sqrt(time_ratio.saturating_mul(SCALE));
+
+        let linear_part = mul_div_saturating(400_000, time_ratio, SCALE)?.saturating_add(600_000);
+        let time_factor = mul_div_saturating(sqrt_ratio, linear_part, SCALE)?;
+
+        mul_div_saturating(deposit_ratio, time_factor, SCALE)?.max(MIN_EXTENSION_RATIO)
+    };
+
+    let capped = extension_ratio.min(MAX_EXTENSION_RATIO);
+
+    to_i64(mul_div(full_duration, capped, SCALE, Rounding::Down)?)
+}
+
+pub fn get_deposit_fee_bps(lock_tier: &LockTier) -> u64 {
+    match lock_tier {
+        LockTier::Unlocked => 50,
+        LockTier::Short => 30,
+        LockTier::Long => 20,
+        LockTier::VeryLong => 10,
+    }
+}
+
+/// Returns (fee, net). The fee is kept by the vault, so it rounds up.
+pub fn calculate_deposit_fee(amount: u64, fee_bps: u64) -> Result<(u64, u64)> {
+    let fee_amount = mul_div(amount, fee_bps, BPS_DENOMINATOR, Rounding::Up)?;
+    Ok((fee_amount, checked_sub(amount, fee_amount)?))
+}
+
+/// Amount that actually landed in the vault during a transfer. Mints that
+/// charge a fee on transfer deliver less than the requested amount.
+pub fn calculate_received_amount(balance_before: u64, balance_after: u64) -> Result<u64> {
+    let received = checked_sub(balance_after, balance_before)?;
+    require!(received > 0, ErrorCode::InvalidAmount);
+    Ok(received)
+}
+
+/// Assets paid out for `shares`, rounded down.
+pub fn calculate_assets_for_shares(
+    shares: u64,
+    total_assets: u64,
+    total_shares: u64,
+) -> Result<u64> {
+    if total_shares == 0 {
+        return Ok(0);
+    }
+    mul_div(shares, total_assets, total_shares, Rounding::Down)
+}
+
+/// Shares are priced against the vault's assets before the deposit landed,
+/// rounded down.
+pub fn calculate_shares_to_mint(
+    net_deposit: u64,
+    total_shares: u64,
+    total_assets: u64,
+) -> Result<u64> {
+    if total_shares == 0 || total_assets == 0 {
+        Ok(net_deposit)
+    } else {
+        mul_div(net_deposit, total_shares, total_assets, Rounding::Down)
+    }
+}
+
+fn integer_sqrt(n: u64) -> u64 {
+    if n == 0 {
+        return 0;
+    }
+    if n < 4 {
+        return 1;
+    }
+
+    let mut x = n / 2;
+    let mut y = (x + n / x) / 2;
+
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub owner: Pubkey,
+    pub asset_mint: Pubkey,
+    pub share_mint: Pubkey,
+    pub nft_collection_address: Pubkey,
+    //
+    pub last_update_time: i64,
+    pub reserve_factor: u64,
+    pub total_reserves: u64,
+    pub total_shares: u64,
+    pub total_locked_shares: u64,
+    pub total_unlocked_shares: u64,
+    //
+    pub bump: u8,
+    //
+    pub risk_manager: Pubkey,
+    pub total_debt: u64,
+    #[max_len(MAX_STRATEGIES)]
+    pub strategies: Vec<Pubkey>,
+    //
+    pub queue_head: u64,
+    pub queue_tail: u64,
+    /// Assets owed to pending withdrawal requests, priced when requested
+    pub total_queued_assets: u64,
+    //
+    pub fee_recipient: Pubkey,
+    pub management_fee_bps: u64,
+    pub performance_fee_bps: u64,
+    pub high_water_mark: u64,
+    //
+    pub limits: VaultLimits,
+    pub tier_locked_shares: [u64; 4],
+    pub epoch_start: i64,
+    pub epoch_withdrawn: u64,
+    //
+    pub compliance_authority: Pubkey,
+    pub allowlist_enabled: bool,
+    //
+    pub version: u8,
+    pub event_seq: u64,
+    /// unique_low's collection NFT; positions need a verified member of it
+    pub nft_collection_mint: Pubkey,
+    /// Fee shares counted in `total_shares` but not yet minted to `fee_recipient`
+    pub pending_fee_shares: u64,
+    pub reserved: [u8; 16],
+}
+
+/// Admin-configurable caps. A value of 0 means "no limit".
+#[derive(Clone, Copy, Default, AnchorDeserialize, AnchorSerialize, InitSpace)]
+pub struct VaultLimits {
+    /// Ceiling on total vault assets
+    pub deposit_cap: u64,
+    /// Ceiling on the asset value of a single NFT position
+    pub max_position_size: u64,
+    /// Length of a withdrawal rate-limit window in seconds
+    pub epoch_duration: i64,
+    /// Assets that may leave the vault per window
+    pub epoch_withdrawal_limit: u64,
+    /// Ceiling on the asset value locked in each tier, indexed by `LockTier`
+    pub tier_caps: [u64; 4],
+}
+
+impl Vault {
+    /// Reserves the next request id at the tail of the withdrawal queue.
+    pub fn enqueue_withdrawal(&mut self, assets: u64) -> Result<u64> {
+        let request_id = self.queue_tail;
+        self.queue_tail = checked_add(self.queue_tail, 1)?;
+        self.total_queued_assets = checked_add(self.total_queued_assets, assets)?;
+        Ok(request_id)
+    }
+
+    /// Fails if paying `amount` out of `idle_assets` would dip into the
+    /// liquidity set aside for queued withdrawals.
+    pub fn check_liquidity(&self, idle_assets: u64, amount: u64) -> Result<()> {
+        require!(
+            idle_assets.saturating_sub(self.total_queued_assets) >= amount,
+            ErrorCode::InsufficientLiquidity
+        );
+        Ok(())
+    }
+
+    pub fn check_allowlist(&self, is_allowlisted: bool) -> Result<()> {
+        require!(
+            !self.allowlist_enabled || is_allowlisted,
+            ErrorCode::NotAllowlisted
+        );
+        Ok(())
+    }
+
+    pub fn check_deposit_cap(&self, total_assets_after: u64) -> Result<()> {
+        let cap = self.limits.deposit_cap;
+        require!(
+            cap == 0 || total_assets_after <= cap,
+            ErrorCode::DepositCapExceeded
+        );
+        Ok(())
+    }
+
+    pub fn check_position_size(
+        &self,
+        position_shares: u64,
+        total_assets_after: u64,
+        total_shares_after: u64,
+    ) -> Result<()> {
+        let cap = self.limits.max_position_size;
+        if cap == 0 {
+            return Ok(());
+        }
+        let position_assets =
+            calculate_assets_for_shares(position_shares, total_assets_after, total_shares_after)?;
+        require!(position_assets <= cap, ErrorCode::PositionLimitExceeded);
+        Ok(())
+    }
+
+    pub fn check_tier_cap(
+        &self,
+        tier: &LockTier,
+        new_shares: u64,
+        total_assets_after: u64,
+        total_shares_after: u64,
+    ) -> Result<()> {
+        let cap = self.limits.tier_caps[*tier as usize];
+        if cap == 0 {
+            return Ok(());
+        }
+        let tier_shares = checked_add(self.tier_locked_shares[*tier as usize], new_shares)?;
+        let tier_assets =
+            calculate_assets_for_shares(tier_shares, total_assets_after, total_shares_after)?;
+        require!(tier_assets <= cap, ErrorCode::TierCapExceeded);
+        Ok(())
+    }
+
+    /// Sequence number for the next event, so indexers can detect gaps.
+    pub fn next_event_seq(&mut self) -> Result<u64> {
+        self.event_seq = checked_add(self.event_seq, 1)?;
+        Ok(self.event_seq)
+    }
+
+    /// Re-books `shares` from one lock tier to another, enforcing the cap of
+    /// the destination tier.
+    pub fn move_tier_shares(
+        &mut self,
+        from: &LockTier,
+        to: &LockTier,
+        shares: u64,
+        total_assets: u64,
+        total_shares: u64,
+    ) -> Result<()> {
+        if from != to && *to != LockTier::Unlocked {
+            self.check_tier_cap(to, shares, total_assets, total_shares)?;
+        }
+        self.rebook_tier_shares(from, to, shares)
+    }
+
+    /// `move_tier_shares` without the cap check, for shares that must move
+    /// even when the destination tier is full.
+    pub fn rebook_tier_shares(
+        &mut self,
+        from: &LockTier,
+        to: &LockTier,
+        shares: u64,
+    ) -> Result<()> {
+        if from == to {
+            return Ok(());
+        }
+        self.release_tier_shares(from, shares)?;
+        self.book_tier_shares(to, shares)
+    }
+
+    /// Counts `shares` toward `tier` and the vault's locked total. Unlocked
+    /// shares are not tracked per tier.
+    pub fn book_tier_shares(&mut self, tier: &LockTier, shares: u64) -> Result<()> {
+        if *tier != LockTier::Unlocked {
+            self.tier_locked_shares[*tier as usize] =
+                checked_add(self.tier_locked_shares[*tier as usize], shares)?;
+            self.total_locked_shares = checked_add(self.total_locked_shares, shares)?;
+        }
+        Ok(())
+    }
+
+    /// Reverses `book_tier_shares` for shares that leave `tier`.
+    pub fn release_tier_shares(&mut self, tier: &LockTier, shares: u64) -> Result<()> {
+        if *tier != LockTier::Unlocked {
+            self.tier_locked_shares[*tier as usize] =
+                checked_sub(self.tier_locked_shares[*tier as usize], shares)?;
+            self.total_locked_shares = checked_sub(self.total_locked_shares, shares)?;
+        }
+        Ok(())
+    }
+
+    /// Fails if `amount` is more than a single rate-limit window may pay out.
+    pub fn check_withdrawal_size(&self, amount: u64) -> Result<()> {
+        let limit = self.limits.epoch_withdrawal_limit;
+        require!(
+            limit == 0 || amount <= limit,
+            ErrorCode::EpochWithdrawalLimitExceeded
+        );
+        Ok(())
+    }
+
+    /// Rolls the rate-limit window forward if it has elapsed and books
+    /// `amount` against it.
+    pub fn record_withdrawal(&mut self, amount: u64, now: i64) -> Result<()> {
+        let limit = self.limits.epoch_withdrawal_limit;
+        if limit == 0 {
+            return Ok(());
+        }
+
+        if now >= checked_add_i64(self.epoch_start, self.limits.epoch_duration)? {
+            self.epoch_start = now;
+            self.epoch_withdrawn = 0;
+        }
+
+        let withdrawn = checked_add(self.epoch_withdrawn, amount)?;
+        require!(withdrawn <= limit, ErrorCode::EpochWithdrawalLimitExceeded);
+        self.epoch_withdrawn = withdrawn;
+        Ok(())
+    }
+
+    pub fn dequeue_assets(&mut self, assets: u64) -> Result<()> {
+        self.total_queued_assets = checked_sub(self.total_queued_assets, assets)?;
+        Ok(())
+    }
+}
+
+/// Grows `account` to `new_len` bytes, topping up rent from `payer`. The new
+/// bytes are zeroed, so appended fields read as their zero value.
+fn resize_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    if account.data_len() >= new_len {
+        return Ok(());
+    }
+    let shortfall = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(account.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    account.resize(new_len)?;
+    Ok(())
+}
+
+/// Emits the fee and the matching reserves increase for a deposit-side fee.
+fn emit_deposit_fee(
+    vault: &mut Vault,
+    vault_key: Pubkey,
+    nft_mint: Pubkey,
+    fee_bps: u64,
+    fee: u64,
+    share_price: u64,
+) -> Result<()> {
+    if fee == 0 {
+        return Ok(());
+    }
+    emit!(FeeCharged {
+        vault: vault_key,
+        nft_mint,
+        fee_bps,
+        fee,
+        share_price,
+        seq: vault.next_event_seq()?,
+    });
+    emit!(ReservesChanged {
+        vault: vault_key,
+        nft_mint,
+        reason: ReserveChangeReason::DepositFee,
+        amount: fee,
+        total_reserves: vault.total_reserves,
+        share_price,
+        seq: vault.next_event_seq()?,
+    });
+    Ok(())
+}
+
+/// Charges the streaming fees accrued since the last charge at the current
+/// share price. Call it before pricing shares; the fee shares count toward
+/// `total_shares` at once and `accrue_fees` mints them to the recipient.
+fn charge_fees(vault: &mut Account<Vault>, token_balance: u64, now: i64) -> Result<()> {
+    let total_assets = get_total_assets(token_balance, vault)?;
+    let accrual = calculate_fee_accrual(
+        total_assets,
+        vault.total_shares,
+        vault.high_water_mark,
+        vault.management_fee_bps,
+        vault.performance_fee_bps,
+        checked_sub_i64(now, vault.last_update_time)?,
+    )?;
+
+    vault.total_shares = checked_add(vault.total_shares, accrual.fee_shares)?;
+    vault.pending_fee_shares = checked_add(vault.pending_fee_shares, accrual.fee_shares)?;
+    vault.high_water_mark = accrual.high_water_mark;
+    vault.last_update_time = now;
+
+    if accrual.fee_shares > 0 {
+        emit!(FeesAccrued {
+            vault: vault.key(),
+            fee_recipient: vault.fee_recipient,
+            management_fee: accrual.management_fee,
+            performance_fee: accrual.performance_fee,
+            fee_shares: accrual.fee_shares,
+            price_per_share: accrual.price_per_share,
+            high_water_mark: accrual.high_water_mark,
+            seq: vault.next_event_seq()?,
+        });
+    }
+    Ok(())
+}
+
+/// Idle and deployed assets, less what queued withdrawals are already owed.
+fn get_total_assets(token_balance: u64, vault: &Vault) -> Result<u64> {
+    checked_sub(
+        checked_add(token_balance, vault.total_debt)?,
+        vault.total_queued_assets,
+    )
+}
+
+/// Moves every share out of `escrow`'s share account into the NFT's own and
+/// closes the emptied account to `rent_destination`.
+fn release_escrowed_shares<'info>(
+    escrow: &Account<'info, PositionEscrow>,
+    escrow_share_token: AccountInfo<'info>,
+    user_share_token: AccountInfo<'info>,
+    rent_destination: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+) -> Result<()> {
+    let seeds: &[&[u8]] = &[
+        POSITION_ESCROW_SEED,
+        escrow.vault.as_ref(),
+        escrow.nft_mint.as_ref(),
+        &[escrow.bump],
+    ];
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            Transfer {
+                from: escrow_share_token.clone(),
+                to: user_share_token,
+                authority: escrow.to_account_info(),
+            },
+            &[seeds],
+        ),
+        escrow.shares,
+    )?;
+    token::close_account(CpiContext::new_with_signer(
+        token_program,
+        CloseAccount {
+            account: escrow_share_token,
+            destination: rent_destination,
+            authority: escrow.to_account_info(),
+        },
+        &[seeds],
+    ))
+}
+
+/// Hands `returning` back to its NFT. `user_info` is empty unless the NFT was
+/// funded again after it came back, in which case the two are merged.
+pub fn restore_position(
+    vault: &mut Vault,
+    user_info: &mut UserInfo,
+    returning: &UserInfo,
+    now: i64,
+) -> Result<()> {
+    if user_info.vault == Pubkey::default() {
+        *user_info = returning.clone();
+        return Ok(());
+    }
+    let (tier, locked_until) = calculate_merged_lock(user_info, returning, now)?;
+
+    // The shares are already in the vault, so a tier cap lowered while they
+    // were away must not strand them in escrow
+    vault.rebook_tier_shares(&user_info.lock_tier, &tier, user_info.shares)?;
+    vault.rebook_tier_shares(&returning.lock_tier, &tier, returning.shares)?;
+
+    user_info.shares = checked_add(user_info.shares, returning.shares)?;
+    user_info.deposit_amount = checked_add(user_info.deposit_amount, returning.deposit_amount)?;
+    user_info.lock_tier = tier;
+    user_info.locked_until = locked_until;
+    Ok(())
+}
+
+/// Whether `escrow`, unique_low's token account for an NFT, holds it. The NFT
+/// is escrowed while its identity lives on another chain, and the position
+/// stays frozen until it comes back.
+pub fn is_bridged_out(escrow: &AccountInfo) -> bool {
+    if escrow.owner != &Token::id() {
+        return false;
+    }
+    let Ok(data) = escrow.try_borrow_data() else {
+        return false;
+    };
+    TokenAccount::try_deserialize(&mut &data[..]).is_ok_and(|escrow| escrow.amount > 0)
+}
+
+/// Whether `metadata` is the Metaplex metadata of an NFT verified into the
+/// `collection_mint` collection. Only the collection's update authority can
+/// verify an item, so this holds after the NFT's mint authority is revoked.
+pub fn is_verified_collection_member(metadata: &AccountInfo, collection_mint: &Pubkey) -> bool {
+    if metadata.owner != &Metadata::id() {
+        return false;
+    }
+    let Ok(data) = metadata.try_borrow_data() else {
+        return false;
+    };
+    MetadataAccount::try_deserialize(&mut &data[..])
+        .ok()
+        .and_then(|metadata| metadata.collection.clone())
+        .is_some_and(|collection| collection.verified && collection.key == *collection_mint)
+}
+
+/// The NFT holder may always act on their position. Anyone else needs an
+/// unexpired approval from that same holder covering `scope`.
+pub fn authorize_position(
+    signer: &Pubkey,
+    holder: &Pubkey,
+    approval: Option<&OperatorApproval>,
+    scope: u8,
+    now: i64,
+) -> Result<()> {
+    if signer == holder {
+        return Ok(());
+    }
+    let approval = approval.ok_or(ErrorCode::UnauthorizedOperator)?;
+    require!(
+        approval.operator == *signer
+            && approval.approver == *holder
+            && approval.scopes & scope == scope
+            && now < approval.expiry,
+        ErrorCode::UnauthorizedOperator
+    );
+    Ok(())
+}
+
+/// Lock terms for `target` after `source` is folded into it. The position with
+/// the later running lock is the base; the other one's deposit extends it the
+/// same way a top-up would. If neither is locked the target's terms are kept.
+pub fn calculate_merged_lock(
+    target: &UserInfo,
+    source: &UserInfo,
+    now: i64,
+) -> Result<(LockTier, i64)> {
+    let is_active =
+        |info: &UserInfo| info.lock_tier != LockTier::Unlocked && info.locked_until > now;
+
+    let (base, other) =
+        if is_active(source) && (!is_active(target) || source.locked_until > target.locked_until) {
+            (source, target)
+        } else {
+            (target, source)
+        };
+
+    if !is_active(base) {
+        return Ok((target.lock_tier, target.locked_until));
+    }
+    if is_active(other) {
+        require!(other.lock_tier == base.lock_tier, ErrorCode::TierMismatch);
+    }
+    require!(base.deposit_amount > 0, ErrorCode::InvalidDepositAmount);
+
+    let extension = if other.deposit_amount == 0 {
+        0
+    } else {
+        calculate_extension(
+            other.deposit_amount,
+            base.deposit_amount,
+            checked_sub_i64(base.locked_until, now)?,
+            get_lock_duration(&base.lock_tier),
+        )?
+    };
+
+    Ok((
+        base.lock_tier,
+        checked_add_i64(base.locked_until, extension)?,
+    ))
+}
+
+/// Shares worth the position's value above `deposit_amount`, rounded down.
+pub fn calculate_harvestable_shares(
+    position_shares: u64,
+    deposit_amount: u64,
+    total_assets: u64,
+    total_shares: u64,
+) -> Result<u64> {
+    if total_assets == 0 {
+        return Ok(0);
+    }
+    let value = calculate_assets_for_shares(position_shares, total_assets, total_shares)?;
+    let gain = value.saturating_sub(deposit_amount);
+    let shares = mul_div(gain, total_shares, total_assets, Rounding::Down)?;
+    Ok(shares.min(position_shares))
+}
+
+#[derive(Clone, Copy, AnchorDeserialize, AnchorSerialize, PartialEq, InitSpace)]
+pub enum WithdrawalStatus {
+    Pending,
+    Cancelled,
+}
+
+/// Per-(vault, NFT, operator) delegation. `approver` is the holder at
+/// approval time, so the approval stops working once the NFT is transferred.
+#[account]
+#[derive(InitSpace)]
+pub struct OperatorApproval {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub approver: Pubkey,
+    pub operator: Pubkey,
+    pub scopes: u8,
+    pub expiry: i64,
+    pub bump: u8,
+}
+
+/// `[POSITION_ESCROW_SEED, vault, nft_mint]`, a position whose NFT lives on
+/// another chain. Its shares sit in the escrow's share ATA and its terms are
+/// kept here until `receive_position` or `reclaim_position` hands them back
+/// to the NFT.
+#[account]
+#[derive(InitSpace)]
+pub struct PositionEscrow {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub deposit_amount: u64,
+    pub shares: u64,
+    pub lock_tier: LockTier,
+    pub locked_until: i64,
+    pub deposit_time: i64,
+    /// When the NFT left, so `reclaim_position` can tell the VAA that
+    /// brought it back from one of an earlier trip
+    pub bridged_at: i64,
+    pub bump: u8,
+}
+
+impl PositionEscrow {
+    pub fn new(user_info: &UserInfo, bridged_at: i64, bump: u8) -> Self {
+        Self {
+            vault: user_info.vault,
+            nft_mint: user_info.nft_mint,
+            deposit_amount: user_info.deposit_amount,
+            shares: user_info.shares,
+            lock_tier: user_info.lock_tier,
+            locked_until: user_info.locked_until,
+            deposit_time: user_info.deposit_time,
+            bridged_at,
+            bump,
+        }
+    }
+
+    /// The escrowed position as it comes home. The lock can only have been
+    /// extended while it was away.
+    pub fn restore(&self, locked_until: i64) -> UserInfo {
+        UserInfo {
+            vault: self.vault,
+            nft_mint: self.nft_mint,
+            deposit_amount: self.deposit_amount,
+            shares: self.shares,
+            locked_until: self.locked_until.max(locked_until),
+            lock_tier: self.lock_tier,
+            deposit_time: self.deposit_time,
+            version: USER_INFO_VERSION,
+            reserved: [0; 32],
+        }
+    }
+}
+
+/// `[CLAIMED_VAA_SEED, vaa_hash]`, created when a VAA's position is received
+/// or reclaimed so it cannot be redeemed twice
+#[account]
+#[derive(InitSpace)]
+pub struct ClaimedVaa {
+    pub emitter_chain: u16,
+    pub sequence: u64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Allowlisted {
+    pub vault: Pubkey,
+    pub wallet: Pubkey,
+    pub added_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct WithdrawalRequest {
+    pub vault: Pubkey,
+    pub id: u64,
+    pub owner: Pubkey,
+    pub nft_mint: Pubkey,
+    pub shares: u64,
+    pub assets: u64,
+    pub requested_at: i64,
+    pub status: WithdrawalStatus,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Strategy {
+    pub vault: Pubkey,
+    pub strategy_program: Pubkey,
+    pub strategy_state: Pubkey,
+    pub strategy_token_account: Pubkey,
+    pub debt_ceiling: u64,
+    pub current_debt: u64,
+    pub last_report: i64,
+    pub bump: u8,
+}
+
+#[derive(Accounts)]
 pub struct InitializeVault<'info> {
     #[account(mut)]
-    pub owner: Signer<'info>,
+    pub owner: Signer<'info>,
+
+    /// SPL Token mints only. A Token-2022 mint, including one with the
+    /// transfer-fee extension, fails this account's owner check.
+    pub asset_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [VAULT_SEED, asset_mint.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = owner,
+        mint::decimals = asset_mint.decimals,
+        mint::authority = vault,
+        mint::freeze_authority = vault,
+    )]
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = asset_mint,
+        associated_token::authority = vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Clone, Copy, AnchorDeserialize, AnchorSerialize, PartialEq, InitSpace)]
+#[repr(u8)]
+pub enum LockTier {
+    Unlocked = 0,
+    Short = 1,
+    Long = 2,
+    VeryLong = 3,
+}
+
+impl TryFrom<u8> for LockTier {
+    type Error = anchor_lang::error::Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(LockTier::Unlocked),
+            1 => Ok(LockTier::Short),
+            2 => Ok(LockTier::Long),
+            3 => Ok(LockTier::VeryLong),
+            _ => Err(ErrorCode::InvalidLockTier.into()),
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct UserInfo {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub deposit_amount: u64,
+    pub shares: u64,
+    pub locked_until: i64,
+    pub lock_tier: LockTier,
+    pub deposit_time: i64,
+    pub version: u8,
+    pub reserved: [u8; 32],
+}
+
+/// JSON-ready attributes of a position, returned by `position_metadata`.
+/// Amounts are in raw asset/share units; `share_price` is scaled by
+/// `PRECISION`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PositionMetadata {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub asset_mint: Pubkey,
+    pub assets: u64,
+    pub shares: u64,
+    pub deposit_amount: u64,
+    pub lock_tier: LockTier,
+    pub locked_until: i64,
+    pub locked: bool,
+    pub share_price: u64,
+}
+
+impl PositionMetadata {
+    pub fn new(
+        vault_key: Pubkey,
+        vault: &Vault,
+        user_info: &UserInfo,
+        idle_assets: u64,
+        now: i64,
+    ) -> Result<Self> {
+        let total_assets = get_total_assets(idle_assets, vault)?;
+        Ok(Self {
+            vault: vault_key,
+            nft_mint: user_info.nft_mint,
+            asset_mint: vault.asset_mint,
+            assets: calculate_assets_for_shares(
+                user_info.shares,
+                total_assets,
+                vault.total_shares,
+            )?,
+            shares: user_info.shares,
+            deposit_amount: user_info.deposit_amount,
+            lock_tier: user_info.lock_tier,
+            locked_until: user_info.locked_until,
+            locked: now < user_info.locked_until,
+            share_price: price_per_share(total_assets, vault.total_shares)?,
+        })
+    }
+
+    /// What travels with the NFT when the position is bridged.
+    pub fn snapshot(&self) -> PositionSnapshot {
+        PositionSnapshot {
+            vault: self.vault,
+            shares: self.shares,
+            assets: self.assets,
+            lock_tier: self.lock_tier as u8,
+            locked_until: self.locked_until,
+        }
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64)]
+pub struct Deposit<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mut, has_one = asset_mint, has_one = share_mint)]
+    pub vault: Account<'info, Vault>,
+
+    /// The NFT collection account (PDA from NFT program)
+    #[account(address = vault.nft_collection_address @ ErrorCode::InvalidNftCollection)]
+    pub nft_collection: Account<'info, Collection>,
+
+    /// ✅ Must own at least 1 NFT where the mint authority is the collection
+    #[account(
+        constraint = user_nft_token.owner == user.key(),
+        constraint = user_nft_token.amount > 0,
+    )]
+    pub user_nft_token: Account<'info, TokenAccount>,
+
+    #[account(
+        constraint = user_nft_token.mint == user_nft_mint.key(),
+        constraint = user_nft_mint.supply == 1 && user_nft_mint.decimals == 0 @ ErrorCode::NotUniqueNft,
+    )]
+    pub user_nft_mint: Account<'info, Mint>,
+
+    /// CHECK: Metaplex metadata of `user_nft_mint`; it must carry the vault's
+    /// collection as a verified collection
+    #[account(
+        seeds = [b"metadata", Metadata::id().as_ref(), user_nft_mint.key().as_ref()],
+        bump,
+        seeds::program = Metadata::id(),
+        constraint = is_verified_collection_member(&user_nft_metadata, &vault.nft_collection_mint) @ ErrorCode::NftNotInCollection,
+    )]
+    pub user_nft_metadata: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub asset_mint: Account<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = asset_mint,
+        associated_token::authority = user
+    )]
+    pub user_asset_token: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = asset_mint, token::authority = vault)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub share_mint: Account<'info, Mint>,
+
+    /// CHECK: This PDA is derived deterministically from user_nft_mint and used as authority for user_share_token.
+    /// It's safe because: 1) Seeds are deterministic, 2) Only used as token account authority, 3) No data stored in this account
+    #[account(
+    seeds = [USER_SHARES_SEED, user_nft_mint.key().as_ref()],
+    bump
+    )]
+    pub user_share_pda: AccountInfo<'info>,
+
+    #[account(
+    init_if_needed,
+    payer = user,
+    associated_token::mint = share_mint,
+    associated_token::authority = user_share_pda
+)]
+    pub user_share_token: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserInfo::INIT_SPACE,
+        seeds = [USER_INFO_SEED, user_nft_mint.key().as_ref(), user_share_token.key().as_ref()],
+        bump
+    )]
+    pub user_info: Account<'info, UserInfo>,
+
+    /// Required only while the vault's allowlist is enabled
+    #[account(
+        seeds = [ALLOWLIST_SEED, vault.key().as_ref(), user.key().as_ref()],
+        bump = allowlist_entry.bump
+    )]
+    pub allowlist_entry: Option<Account<'info, Allowlisted>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64)]
+pub struct Lock<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mut, has_one = asset_mint, has_one = share_mint)]
+    pub vault: Account<'info, Vault>,
+
+    /// The NFT collection account (PDA from NFT program)
+    #[account(address = vault.nft_collection_address @ ErrorCode::InvalidNftCollection)]
+    pub nft_collection: Account<'info, Collection>,
+
+    /// ✅ Must own at least 1 NFT where the mint authority is the collection
+    /// Held by the position owner; `user` is either that owner or an approved operator
+    #[account(constraint = user_nft_token.amount > 0)]
+    pub user_nft_token: Account<'info, TokenAccount>,
+
+    #[account(
+        constraint = user_nft_token.mint == user_nft_mint.key(),
+        constraint = user_nft_mint.supply == 1 && user_nft_mint.decimals == 0 @ ErrorCode::NotUniqueNft,
+    )]
+    pub user_nft_mint: Account<'info, Mint>,
+
+    /// CHECK: Metaplex metadata of `user_nft_mint`; it must carry the vault's
+    /// collection as a verified collection
+    #[account(
+        seeds = [b"metadata", Metadata::id().as_ref(), user_nft_mint.key().as_ref()],
+        bump,
+        seeds::program = Metadata::id(),
+        constraint = is_verified_collection_member(&user_nft_metadata, &vault.nft_collection_mint) @ ErrorCode::NftNotInCollection,
+    )]
+    pub user_nft_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: unique_low's escrow for `user_nft_mint`, usually absent
+    #[account(
+        seeds = [
+            vault.nft_collection_address.as_ref(),
+            token_program.key().as_ref(),
+            user_nft_mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = associated_token_program.key(),
+        constraint = !is_bridged_out(&nft_escrow) @ ErrorCode::PositionBridgedOut,
+    )]
+    pub nft_escrow: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub asset_mint: Account<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = asset_mint,
+        associated_token::authority = user
+    )]
+    pub user_asset_token: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = asset_mint, token::authority = vault)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub share_mint: Account<'info, Mint>,
+
+    /// CHECK: This PDA is derived deterministically from user_nft_mint and used as authority for user_share_token.
+    /// It's safe because: 1) Seeds are deterministic, 2) Only used as token account authority, 3) No data stored in this account
+    #[account(
+    seeds = [USER_SHARES_SEED, user_nft_mint.key().as_ref()],
+    bump
+    )]
+    pub user_share_pda: AccountInfo<'info>,
+
+    #[account(
+    init_if_needed,
+    payer = user,
+    associated_token::mint = share_mint,
+    associated_token::authority = user_share_pda
+)]
+    pub user_share_token: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserInfo::INIT_SPACE,
+        seeds = [USER_INFO_SEED, user_nft_mint.key().as_ref(), user_share_token.key().as_ref()],
+        bump
+    )]
+    pub user_info: Account<'info, UserInfo>,
+
+    /// Required only while the vault's allowlist is enabled
+    #[account(
+        seeds = [ALLOWLIST_SEED, vault.key().as_ref(), user.key().as_ref()],
+        bump = allowlist_entry.bump
+    )]
+    pub allowlist_entry: Option<Account<'info, Allowlisted>>,
+
+    /// Required only when `user` is not the NFT holder
+    #[account(
+        seeds = [OPERATOR_SEED, vault.key().as_ref(), user_nft_mint.key().as_ref(), user.key().as_ref()],
+        bump = operator_approval.bump
+    )]
+    pub operator_approval: Option<Account<'info, OperatorApproval>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+#[instruction(shares: u64)]
+pub struct Withdraw<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mut, has_one = asset_mint, has_one = share_mint)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(address = vault.nft_collection_address @ ErrorCode::InvalidNftCollection)]
+    pub nft_collection: Account<'info, Collection>,
+
+    #[account(
+        constraint = user_nft_token.owner == user.key(),
+        constraint = user_nft_token.amount > 0,
+    )]
+    pub user_nft_token: Account<'info, TokenAccount>,
+
+    #[account(
+        constraint = user_nft_token.mint == user_nft_mint.key(),
+        constraint = user_nft_mint.supply == 1 && user_nft_mint.decimals == 0 @ ErrorCode::NotUniqueNft,
+    )]
+    pub user_nft_mint: Account<'info, Mint>,
+
+    /// CHECK: Metaplex metadata of `user_nft_mint`; it must carry the vault's
+    /// collection as a verified collection
+    #[account(
+        seeds = [b"metadata", Metadata::id().as_ref(), user_nft_mint.key().as_ref()],
+        bump,
+        seeds::program = Metadata::id(),
+        constraint = is_verified_collection_member(&user_nft_metadata, &vault.nft_collection_mint) @ ErrorCode::NftNotInCollection,
+    )]
+    pub user_nft_metadata: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub asset_mint: Account<'info, Mint>,
+
+    /// CHECK: Any wallet chosen by the NFT holder, only used as the payout authority
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = asset_mint,
+        associated_token::authority = recipient
+    )]
+    pub recipient_asset_token: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = asset_mint, token::authority = vault)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub share_mint: Account<'info, Mint>,
+
+    /// CHECK: This PDA is derived deterministically from user_nft_mint and used as authority for user_share_token.
+    /// It's safe because: 1) Seeds are deterministic, 2) Only used as token account authority, 3) No data stored in this account
+    #[account(
+    seeds = [USER_SHARES_SEED, user_nft_mint.key().as_ref()],
+    bump
+    )]
+    pub user_share_pda: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = share_mint,
+        associated_token::authority = user_share_pda
+    )]
+    pub user_share_token: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserInfo::INIT_SPACE,
+        seeds = [USER_INFO_SEED, user_nft_mint.key().as_ref(), user_share_token.key().as_ref()],
+        bump
+    )]
+    pub user_info: Account<'info, UserInfo>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+pub fn get_lock_duration(tier: &LockTier) -> i64 {
+    match tier {
+        LockTier::Unlocked => 0,
+        LockTier::Short => 30 * 24 * 60 * 60,
+        LockTier::Long => 6 * 30 * 24 * 60 * 60,
+        LockTier::VeryLong => 12 * 30 * 24 * 60 * 60,
+    }
+}
+
+pub fn calculate_ratio_based_duration(
+    existing_amount: u64,
+    new_amount: u64,
+    full_duration: i64,
+) -> Result<i64> {
+    require!(existing_amount > 0, ErrorCode::InvalidDepositAmount);
+    let full_duration = to_u64(full_duration)?;
+
+    // Calculate ratio with scaling for precision
+    let ratio = mul_div_saturating(new_amount, SCALE, existing_amount)?;
+
+    // Calculate duration
+    let duration = mul_div_saturating(full_duration, ratio, SCALE)?;
+
+    // Apply bounds: min 1 day, max full duration
+    let bounded_duration = duration.max(MIN_LOCK_DURATION as u64).min(full_duration);
+
+    to_i64(bounded_duration)
+}
+
+#[derive(Accounts)]
+pub struct CloseVault<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [VAULT_SEED, asset_mint.key().as_ref(), authority.key().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    pub asset_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RequestWithdrawal<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(address = vault.nft_collection_address @ ErrorCode::InvalidNftCollection)]
+    pub nft_collection: Account<'info, Collection>,
+
+    #[account(
+        constraint = user_nft_token.owner == user.key(),
+        constraint = user_nft_token.amount > 0,
+    )]
+    pub user_nft_token: Account<'info, TokenAccount>,
+
+    #[account(
+        constraint = user_nft_token.mint == user_nft_mint.key(),
+        constraint = user_nft_mint.supply == 1 && user_nft_mint.decimals == 0 @ ErrorCode::NotUniqueNft,
+    )]
+    pub user_nft_mint: Account<'info, Mint>,
+
+    /// CHECK: Metaplex metadata of `user_nft_mint`; it must carry the vault's
+    /// collection as a verified collection
+    #[account(
+        seeds = [b"metadata", Metadata::id().as_ref(), user_nft_mint.key().as_ref()],
+        bump,
+        seeds::program = Metadata::id(),
+        constraint = is_verified_collection_member(&user_nft_metadata, &vault.nft_collection_mint) @ ErrorCode::NftNotInCollection,
+    )]
+    pub user_nft_metadata: UncheckedAccount<'info>,
+
+    #[account(address = vault.asset_mint)]
+    pub asset_mint: Account<'info, Mint>,
+
+    #[account(
+        associated_token::mint = asset_mint,
+        associated_token::authority = vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut, address = vault.share_mint)]
+    pub share_mint: Account<'info, Mint>,
+
+    /// CHECK: This PDA is derived deterministically from user_nft_mint and used as authority for user_share_token.
+    #[account(
+        seeds = [USER_SHARES_SEED, user_nft_mint.key().as_ref()],
+        bump
+    )]
+    pub user_share_pda: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = share_mint,
+        associated_token::authority = user_share_pda
+    )]
+    pub user_share_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [USER_INFO_SEED, user_nft_mint.key().as_ref(), user_share_token.key().as_ref()],
+        bump
+    )]
+    pub user_info: Account<'info, UserInfo>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + WithdrawalRequest::INIT_SPACE,
+        seeds = [WITHDRAWAL_REQUEST_SEED, vault.key().as_ref(), &vault.queue_tail.to_le_bytes()],
+        bump
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelWithdrawal<'info> {
+    pub user: Signer<'info>,
+
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+
+    /// Current holder of the position NFT
+    #[account(
+        constraint = user_nft_token.owner == user.key(),
+        constraint = user_nft_token.amount > 0,
+        constraint = user_nft_token.mint == withdrawal_request.nft_mint,
+    )]
+    pub user_nft_token: Account<'info, TokenAccount>,
+
+    #[account(
+        associated_token::mint = vault.asset_mint,
+        associated_token::authority = vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut, address = vault.share_mint)]
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = share_mint,
+        associated_token::authority = user_share_pda
+    )]
+    pub user_share_token: Account<'info, TokenAccount>,
+
+    /// CHECK: Share authority PDA for the request's NFT
+    #[account(
+        seeds = [USER_SHARES_SEED, withdrawal_request.nft_mint.as_ref()],
+        bump
+    )]
+    pub user_share_pda: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [USER_INFO_SEED, withdrawal_request.nft_mint.as_ref(), user_share_token.key().as_ref()],
+        bump
+    )]
+    pub user_info: Account<'info, UserInfo>,
+
+    #[account(mut, has_one = vault)]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+
+    /// CHECK: unique_low's escrow for the request's NFT, usually absent
+    #[account(
+        seeds = [
+            vault.nft_collection_address.as_ref(),
+            token_program.key().as_ref(),
+            withdrawal_request.nft_mint.as_ref()
+        ],
+        bump,
+        seeds::program = associated_token_program.key(),
+        constraint = !is_bridged_out(&nft_escrow) @ ErrorCode::PositionBridgedOut,
+    )]
+    pub nft_escrow: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessQueue<'info> {
+    #[account(mut, has_one = asset_mint)]
+    pub vault: Account<'info, Vault>,
+
+    pub asset_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = asset_mint,
+        associated_token::authority = vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = vault,
+        has_one = owner,
+        seeds = [WITHDRAWAL_REQUEST_SEED, vault.key().as_ref(), &vault.queue_head.to_le_bytes()],
+        bump = withdrawal_request.bump
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+
+    /// CHECK: unique_low's escrow for the request's NFT, usually absent
+    #[account(
+        seeds = [
+            vault.nft_collection_address.as_ref(),
+            token_program.key().as_ref(),
+            withdrawal_request.nft_mint.as_ref()
+        ],
+        bump,
+        seeds::program = associated_token_program.key(),
+        constraint = !is_bridged_out(&nft_escrow) @ ErrorCode::PositionBridgedOut,
+    )]
+    pub nft_escrow: UncheckedAccount<'info>,
+
+    /// CHECK: Receives the request's rent, matched against `withdrawal_request.owner`
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = asset_mint,
+        associated_token::authority = owner,
+    )]
+    pub recipient: Account<'info, TokenAccount>,
+
+    /// Whoever runs the crank; funds `recipient` if it has to be recreated
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct SetCompliance<'info> {
+    #[account(mut, has_one = owner)]
+    pub vault: Account<'info, Vault>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct AddToAllowlist<'info> {
+    #[account(mut)]
+    pub compliance_authority: Signer<'info>,
+
+    #[account(mut, has_one = compliance_authority)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = compliance_authority,
+        space = 8 + Allowlisted::INIT_SPACE,
+        seeds = [ALLOWLIST_SEED, vault.key().as_ref(), wallet.as_ref()],
+        bump
+    )]
+    pub allowlist_entry: Account<'info, Allowlisted>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFromAllowlist<'info> {
+    #[account(mut)]
+    pub compliance_authority: Signer<'info>,
+
+    #[account(mut, has_one = compliance_authority)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        close = compliance_authority,
+        has_one = vault,
+        seeds = [ALLOWLIST_SEED, vault.key().as_ref(), allowlist_entry.wallet.as_ref()],
+        bump = allowlist_entry.bump
+    )]
+    pub allowlist_entry: Account<'info, Allowlisted>,
+}
+
+#[derive(Accounts)]
+#[instruction(nft_mint: Pubkey)]
+pub struct DepositFor<'info> {
+    /// Funder; does not need to hold the NFT
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mut, has_one = asset_mint, has_one = share_mint)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(address = vault.nft_collection_address @ ErrorCode::InvalidNftCollection)]
+    pub nft_collection: Account<'info, Collection>,
+
+    #[account(
+        address = nft_mint,
+        constraint = user_nft_mint.supply == 1 && user_nft_mint.decimals == 0 @ ErrorCode::NotUniqueNft,
+    )]
+    pub user_nft_mint: Account<'info, Mint>,
+
+    /// CHECK: Metaplex metadata of `user_nft_mint`; it must carry the vault's
+    /// collection as a verified collection
+    #[account(
+        seeds = [b"metadata", Metadata::id().as_ref(), user_nft_mint.key().as_ref()],
+        bump,
+        seeds::program = Metadata::id(),
+        constraint = is_verified_collection_member(&user_nft_metadata, &vault.nft_collection_mint) @ ErrorCode::NftNotInCollection,
+    )]
+    pub user_nft_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: unique_low's escrow for `user_nft_mint`, usually absent
+    #[account(
+        seeds = [
+            vault.nft_collection_address.as_ref(),
+            token_program.key().as_ref(),
+            user_nft_mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = associated_token_program.key(),
+        constraint = !is_bridged_out(&nft_escrow) @ ErrorCode::PositionBridgedOut,
+    )]
+    pub nft_escrow: UncheckedAccount<'info>,
+
+    pub asset_mint: Account<'info, Mint>,
+
+    #[account(mut, token::mint = asset_mint, token::authority = user)]
+    pub user_asset_token: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = asset_mint, token::authority = vault)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub share_mint: Account<'info, Mint>,
+
+    /// CHECK: Share authority PDA for `user_nft_mint`, only used to derive user_share_token
+    #[account(seeds = [USER_SHARES_SEED, user_nft_mint.key().as_ref()], bump)]
+    pub user_share_pda: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = share_mint,
+        associated_token::authority = user_share_pda
+    )]
+    pub user_share_token: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserInfo::INIT_SPACE,
+        seeds = [USER_INFO_SEED, user_nft_mint.key().as_ref(), user_share_token.key().as_ref()],
+        bump
+    )]
+    pub user_info: Account<'info, UserInfo>,
+
+    /// Required only while the vault's allowlist is enabled
+    #[account(
+        seeds = [ALLOWLIST_SEED, vault.key().as_ref(), user.key().as_ref()],
+        bump = allowlist_entry.bump
+    )]
+    pub allowlist_entry: Option<Account<'info, Allowlisted>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+#[instruction(operator: Pubkey)]
+pub struct ApproveOperator<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(address = vault.nft_collection_address @ ErrorCode::InvalidNftCollection)]
+    pub nft_collection: Account<'info, Collection>,
+
+    #[account(
+        constraint = user_nft_token.owner == holder.key(),
+        constraint = user_nft_token.amount > 0,
+        constraint = user_nft_token.mint == user_nft_mint.key(),
+    )]
+    pub user_nft_token: Account<'info, TokenAccount>,
+
+    pub user_nft_mint: Account<'info, Mint>,
+
+    /// CHECK: Metaplex metadata of `user_nft_mint`; it must carry the vault's
+    /// collection as a verified collection
+    #[account(
+        seeds = [b"metadata", Metadata::id().as_ref(), user_nft_mint.key().as_ref()],
+        bump,
+        seeds::program = Metadata::id(),
+        constraint = is_verified_collection_member(&user_nft_metadata, &vault.nft_collection_mint) @ ErrorCode::NftNotInCollection,
+    )]
+    pub user_nft_metadata: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = 8 + OperatorApproval::INIT_SPACE,
+        seeds = [OPERATOR_SEED, vault.key().as_ref(), user_nft_mint.key().as_ref(), operator.as_ref()],
+        bump
+    )]
+    pub operator_approval: Account<'info, OperatorApproval>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeOperator<'info> {
+    #[account(mut)]
+    pub approver: Signer<'info>,
+
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+
+    /// CHECK: Metaplex metadata of the approval's NFT; it must carry the
+    /// vault's collection as a verified collection
+    #[account(
+        seeds = [b"metadata", Metadata::id().as_ref(), operator_approval.nft_mint.as_ref()],
+        bump,
+        seeds::program = Metadata::id(),
+        constraint = is_verified_collection_member(&user_nft_metadata, &vault.nft_collection_mint) @ ErrorCode::NftNotInCollection,
+    )]
+    pub user_nft_metadata: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = approver,
+        has_one = approver,
+        has_one = vault,
+        seeds = [OPERATOR_SEED, vault.key().as_ref(), operator_approval.nft_mint.as_ref(), operator_approval.operator.as_ref()],
+        bump = operator_approval.bump
+    )]
+    pub operator_approval: Account<'info, OperatorApproval>,
+}
+
+#[derive(Accounts)]
+pub struct Relock<'info> {
+    pub user: Signer<'info>,
+
+    #[account(mut, has_one = share_mint)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(address = vault.nft_collection_address @ ErrorCode::InvalidNftCollection)]
+    pub nft_collection: Account<'info, Collection>,
+
+    #[account(
+        constraint = user_nft_token.amount > 0,
+        constraint = user_nft_token.mint == user_nft_mint.key(),
+    )]
+    pub user_nft_token: Account<'info, TokenAccount>,
+
+    pub user_nft_mint: Account<'info, Mint>,
+
+    /// CHECK: Metaplex metadata of `user_nft_mint`; it must carry the vault's
+    /// collection as a verified collection
+    #[account(
+        seeds = [b"metadata", Metadata::id().as_ref(), user_nft_mint.key().as_ref()],
+        bump,
+        seeds::program = Metadata::id(),
+        constraint = is_verified_collection_member(&user_nft_metadata, &vault.nft_collection_mint) @ ErrorCode::NftNotInCollection,
+    )]
+    pub user_nft_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: unique_low's escrow for `user_nft_mint`, usually absent
+    #[account(
+        seeds = [
+            vault.nft_collection_address.as_ref(),
+            token_program.key().as_ref(),
+            user_nft_mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = associated_token_program.key(),
+        constraint = !is_bridged_out(&nft_escrow) @ ErrorCode::PositionBridgedOut,
+    )]
+    pub nft_escrow: UncheckedAccount<'info>,
+
+    #[account(token::mint = vault.asset_mint, token::authority = vault)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    pub share_mint: Account<'info, Mint>,
+
+    /// CHECK: Share authority PDA for `user_nft_mint`, only used to derive user_share_token
+    #[account(seeds = [USER_SHARES_SEED, user_nft_mint.key().as_ref()], bump)]
+    pub user_share_pda: AccountInfo<'info>,
+
+    #[account(
+        associated_token::mint = share_mint,
+        associated_token::authority = user_share_pda
+    )]
+    pub user_share_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [USER_INFO_SEED, user_nft_mint.key().as_ref(), user_share_token.key().as_ref()],
+        bump
+    )]
+    pub user_info: Account<'info, UserInfo>,
+
+    /// Required only when `user` is not the NFT holder
+    #[account(
+        seeds = [OPERATOR_SEED, vault.key().as_ref(), user_nft_mint.key().as_ref(), user.key().as_ref()],
+        bump = operator_approval.bump
+    )]
+    pub operator_approval: Option<Account<'info, OperatorApproval>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct Harvest<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mut, has_one = asset_mint, has_one = share_mint)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(address = vault.nft_collection_address @ ErrorCode::InvalidNftCollection)]
+    pub nft_collection: Account<'info, Collection>,
+
+    #[account(
+        constraint = user_nft_token.amount > 0,
+        constraint = user_nft_token.mint == user_nft_mint.key(),
+    )]
+    pub user_nft_token: Account<'info, TokenAccount>,
+
+    pub user_nft_mint: Account<'info, Mint>,
+
+    /// CHECK: Metaplex metadata of `user_nft_mint`; it must carry the vault's
+    /// collection as a verified collection
+    #[account(
+        seeds = [b"metadata", Metadata::id().as_ref(), user_nft_mint.key().as_ref()],
+        bump,
+        seeds::program = Metadata::id(),
+        constraint = is_verified_collection_member(&user_nft_metadata, &vault.nft_collection_mint) @ ErrorCode::NftNotInCollection,
+    )]
+    pub user_nft_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: unique_low's escrow for `user_nft_mint`, usually absent
+    #[account(
+        seeds = [
+            vault.nft_collection_address.as_ref(),
+            token_program.key().as_ref(),
+            user_nft_mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = associated_token_program.key(),
+        constraint = !is_bridged_out(&nft_escrow) @ ErrorCode::PositionBridgedOut,
+    )]
+    pub nft_escrow: UncheckedAccount<'info>,
+
+    /// CHECK: Current NFT holder, only used as the payout authority
+    #[account(address = user_nft_token.owner)]
+    pub holder: UncheckedAccount<'info>,
+
+    pub asset_mint: Account<'info, Mint>,
+
+    /// Payout always goes to the holder's ATA, never to the signer
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = asset_mint,
+        associated_token::authority = holder
+    )]
+    pub holder_asset_token: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = asset_mint, token::authority = vault)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub share_mint: Account<'info, Mint>,
+
+    /// CHECK: Share authority PDA for `user_nft_mint`, signs the burn
+    #[account(seeds = [USER_SHARES_SEED, user_nft_mint.key().as_ref()], bump)]
+    pub user_share_pda: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = share_mint,
+        associated_token::authority = user_share_pda
+    )]
+    pub user_share_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [USER_INFO_SEED, user_nft_mint.key().as_ref(), user_share_token.key().as_ref()],
+        bump
+    )]
+    pub user_info: Account<'info, UserInfo>,
+
+    /// Required only when `user` is not the NFT holder
+    #[account(
+        seeds = [OPERATOR_SEED, vault.key().as_ref(), user_nft_mint.key().as_ref(), user.key().as_ref()],
+        bump = operator_approval.bump
+    )]
+    pub operator_approval: Option<Account<'info, OperatorApproval>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct PositionMetadataView<'info> {
+    /// Writable so the fees accrued so far are charged before pricing
+    #[account(mut, has_one = share_mint)]
+    pub vault: Account<'info, Vault>,
+
+    pub user_nft_mint: Account<'info, Mint>,
+
+    #[account(token::mint = vault.asset_mint, token::authority = vault)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    pub share_mint: Account<'info, Mint>,
+
+    /// CHECK: Share authority PDA for `user_nft_mint`
+    #[account(seeds = [USER_SHARES_SEED, user_nft_mint.key().as_ref()], bump)]
+    pub user_share_pda: AccountInfo<'info>,
+
+    #[account(
+        associated_token::mint = share_mint,
+        associated_token::authority = user_share_pda
+    )]
+    pub user_share_token: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [USER_INFO_SEED, user_nft_mint.key().as_ref(), user_share_token.key().as_ref()],
+        bump,
+    )]
+    pub user_info: Account<'info, UserInfo>,
+}
+
+#[derive(Accounts)]
+pub struct BridgePosition<'info> {
+    /// Current NFT holder
+    #[account(mut)]
+    pub user: Signer<'info>,
 
-    pub asset_mint: Account<'info, Mint>,
+    #[account(mut, has_one = share_mint)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(token::mint = vault.asset_mint, token::authority = vault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(address = vault.nft_collection_address @ ErrorCode::InvalidNftCollection)]
+    pub nft_collection: Box<Account<'info, Collection>>,
+
+    #[account(mut)]
+    pub user_nft_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = user_nft_token.owner == user.key(),
+        constraint = user_nft_token.amount > 0,
+        constraint = user_nft_token.mint == user_nft_mint.key(),
+    )]
+    pub user_nft_token: Box<Account<'info, TokenAccount>>,
+
+    pub share_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Share authority PDA for `user_nft_mint`, signs the share transfer
+    #[account(seeds = [USER_SHARES_SEED, user_nft_mint.key().as_ref()], bump)]
+    pub user_share_pda: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = share_mint,
+        associated_token::authority = user_share_pda
+    )]
+    pub user_share_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [USER_INFO_SEED, user_nft_mint.key().as_ref(), user_share_token.key().as_ref()],
+        bump
+    )]
+    pub user_info: Box<Account<'info, UserInfo>>,
 
     #[account(
         init,
-        payer = owner,
-        space = 8 + 32 *4 + 8*7 + 1,
-        seeds = [VAULT_SEED, asset_mint.key().as_ref(), owner.key().as_ref()],
+        payer = user,
+        space = 8 + PositionEscrow::INIT_SPACE,
+        seeds = [POSITION_ESCROW_SEED, vault.key().as_ref(), user_nft_mint.key().as_ref()],
         bump
     )]
-    pub vault: Account<'info, Vault>,
+    pub position_escrow: Box<Account<'info, PositionEscrow>>,
 
     #[account(
         init,
-        payer = owner,
-        mint::decimals = asset_mint.decimals,
-        mint::authority = vault,
-        mint::freeze_authority = vault,
+        payer = user,
+        associated_token::mint = share_mint,
+        associated_token::authority = position_escrow
     )]
-    pub share_mint: Account<'info, Mint>,
+    pub escrow_share_token: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Signs for the position snapshot, which the NFT program only
+    /// accepts from this PDA
+    #[account(seeds = [POSITION_AUTHORITY_SEED], bump)]
+    pub position_authority: UncheckedAccount<'info>,
+
+    /// CHECK: The NFT program's emitter for the target chain, validated by the
+    /// NFT program
+    pub nft_foreign_emitter: UncheckedAccount<'info>,
+
+    /// CHECK: Registry records of `user_nft_mint`, validated by the NFT program
+    pub nft_mint_record: UncheckedAccount<'info>,
+
+    /// CHECK: See `nft_mint_record`
+    #[account(mut)]
+    pub nft_unique_id_record: UncheckedAccount<'info>,
+
+    /// CHECK: The NFT program's escrow ATA for `user_nft_mint`, created by it
+    #[account(mut)]
+    pub nft_escrow: UncheckedAccount<'info>,
+
+    /// CHECK: Core bridge accounts of the NFT program's message, validated by
+    /// the NFT program
+    pub wormhole_emitter: UncheckedAccount<'info>,
+
+    /// CHECK: See `wormhole_emitter`
+    #[account(mut)]
+    pub wormhole_sequence: UncheckedAccount<'info>,
+
+    /// CHECK: See `wormhole_emitter`
+    #[account(mut)]
+    pub wormhole_message: UncheckedAccount<'info>,
+
+    /// CHECK: See `wormhole_emitter`
+    #[account(mut)]
+    pub wormhole_bridge: UncheckedAccount<'info>,
+
+    /// CHECK: See `wormhole_emitter`
+    #[account(mut)]
+    pub wormhole_fee_collector: UncheckedAccount<'info>,
+
+    /// CHECK: See `wormhole_emitter`
+    pub wormhole_program: UncheckedAccount<'info>,
+
+    pub nft_program: Program<'info, UniqueLow>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub clock: Sysvar<'info, Clock>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(vaa_hash: [u8; 32])]
+pub struct ReceivePosition<'info> {
+    /// Relayer, pays for a recreated position and takes the escrow's rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, has_one = share_mint)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(address = vault.nft_collection_address @ ErrorCode::InvalidNftCollection)]
+    pub nft_collection: Box<Account<'info, Collection>>,
+
+    /// CHECK: The collection's core bridge
+    #[account(
+        executable,
+        address = nft_collection.wormhole_program_id @ unique_low::ErrorCode::InvalidWormholeProgram
+    )]
+    pub wormhole_program: UncheckedAccount<'info>,
+
+    /// CHECK: Guardian-verified VAA posted by the core bridge. Its layout is
+    /// checked by `verify_inbound_vaa`
+    #[account(
+        seeds = [WORMHOLE_POSTED_VAA_SEED, vaa_hash.as_ref()],
+        bump,
+        seeds::program = wormhole_program.key(),
+        owner = wormhole_program.key() @ unique_low::ErrorCode::InvalidPostedVaa
+    )]
+    pub posted_vaa: UncheckedAccount<'info>,
+
+    /// The NFT program's trusted contract on the VAA's chain
+    #[account(
+        seeds = [FOREIGN_EMITTER_SEED, foreign_emitter.chain.to_le_bytes().as_ref()],
+        bump = foreign_emitter.bump,
+        seeds::program = nft_program.key()
+    )]
+    pub foreign_emitter: Box<Account<'info, ForeignEmitter>>,
 
     #[account(
         init,
-        payer = owner,
-        associated_token::mint = asset_mint,
-        associated_token::authority = vault,
+        payer = payer,
+        space = 8 + ClaimedVaa::INIT_SPACE,
+        seeds = [CLAIMED_VAA_SEED, vaa_hash.as_ref()],
+        bump
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    pub claimed_vaa: Box<Account<'info, ClaimedVaa>>,
+
+    #[account(
+        seeds = [
+            UNIQUE_ID_SEED,
+            PostedMessageData::load_posted_vaa(&posted_vaa)?.mint_payload()?.unique_id.as_ref()
+        ],
+        bump = unique_id_record.bump,
+        seeds::program = nft_program.key()
+    )]
+    pub unique_id_record: Box<Account<'info, UniqueIdRecord>>,
+
+    #[account(address = unique_id_record.mint)]
+    pub user_nft_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        close = payer,
+        seeds = [POSITION_ESCROW_SEED, vault.key().as_ref(), user_nft_mint.key().as_ref()],
+        bump = position_escrow.bump
+    )]
+    pub position_escrow: Box<Account<'info, PositionEscrow>>,
+
+    #[account(
+        mut,
+        associated_token::mint = share_mint,
+        associated_token::authority = position_escrow
+    )]
+    pub escrow_share_token: Box<Account<'info, TokenAccount>>,
+
+    pub share_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Share authority PDA for `user_nft_mint`
+    #[account(seeds = [USER_SHARES_SEED, user_nft_mint.key().as_ref()], bump)]
+    pub user_share_pda: AccountInfo<'info>,
 
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = share_mint,
+        associated_token::authority = user_share_pda
+    )]
+    pub user_share_token: Box<Account<'info, TokenAccount>>,
+
+    /// Recreated from the escrow, unless the NFT was funded again after it
+    /// came back
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserInfo::INIT_SPACE,
+        seeds = [USER_INFO_SEED, user_nft_mint.key().as_ref(), user_share_token.key().as_ref()],
+        bump
+    )]
+    pub user_info: Box<Account<'info, UserInfo>>,
+
+    pub nft_program: Program<'info, UniqueLow>,
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
-#[derive(Clone, Copy, AnchorDeserialize, AnchorSerialize, PartialEq)]
-#[repr(u8)]
-pub enum LockTier {
-    Unlocked = 0,
-    Short = 1,
-    Long = 2,
-    VeryLong = 3,
+#[derive(Accounts)]
+#[instruction(vaa_hash: [u8; 32])]
+pub struct ReclaimPosition<'info> {
+    /// Current NFT holder and the VAA's recipient, pays for a recreated
+    /// position and takes the escrow's rent
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mut, has_one = share_mint)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(address = vault.nft_collection_address @ ErrorCode::InvalidNftCollection)]
+    pub nft_collection: Box<Account<'info, Collection>>,
+
+    /// CHECK: The collection's core bridge
+    #[account(
+        executable,
+        address = nft_collection.wormhole_program_id @ unique_low::ErrorCode::InvalidWormholeProgram
+    )]
+    pub wormhole_program: UncheckedAccount<'info>,
+
+    /// CHECK: Guardian-verified VAA posted by the core bridge. Its layout is
+    /// checked by `verify_release_vaa`
+    #[account(
+        seeds = [WORMHOLE_POSTED_VAA_SEED, vaa_hash.as_ref()],
+        bump,
+        seeds::program = wormhole_program.key(),
+        owner = wormhole_program.key() @ unique_low::ErrorCode::InvalidPostedVaa
+    )]
+    pub posted_vaa: UncheckedAccount<'info>,
+
+    /// The NFT program's trusted contract on the VAA's chain
+    #[account(
+        seeds = [FOREIGN_EMITTER_SEED, foreign_emitter.chain.to_le_bytes().as_ref()],
+        bump = foreign_emitter.bump,
+        seeds::program = nft_program.key()
+    )]
+    pub foreign_emitter: Box<Account<'info, ForeignEmitter>>,
+
+    /// unique_low's record that the VAA released the NFT from its escrow
+    #[account(
+        seeds = [unique_low::CLAIMED_VAA_SEED, vaa_hash.as_ref()],
+        bump = released_vaa.bump,
+        seeds::program = nft_program.key()
+    )]
+    pub released_vaa: Box<Account<'info, unique_low::ClaimedVaa>>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + ClaimedVaa::INIT_SPACE,
+        seeds = [CLAIMED_VAA_SEED, vaa_hash.as_ref()],
+        bump
+    )]
+    pub claimed_vaa: Box<Account<'info, ClaimedVaa>>,
+
+    #[account(
+        seeds = [
+            UNIQUE_ID_SEED,
+            PostedMessageData::load_posted_vaa(&posted_vaa)?.mint_payload()?.unique_id.as_ref()
+        ],
+        bump = unique_id_record.bump,
+        seeds::program = nft_program.key()
+    )]
+    pub unique_id_record: Box<Account<'info, UniqueIdRecord>>,
+
+    /// Holding the NFT means it has left unique_low's escrow
+    #[account(
+        constraint = user_nft_token.owner == user.key(),
+        constraint = user_nft_token.amount > 0,
+        constraint = user_nft_token.mint == user_nft_mint.key(),
+    )]
+    pub user_nft_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(address = unique_id_record.mint)]
+    pub user_nft_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [POSITION_ESCROW_SEED, vault.key().as_ref(), user_nft_mint.key().as_ref()],
+        bump = position_escrow.bump
+    )]
+    pub position_escrow: Box<Account<'info, PositionEscrow>>,
+
+    #[account(
+        mut,
+        associated_token::mint = share_mint,
+        associated_token::authority = position_escrow
+    )]
+    pub escrow_share_token: Box<Account<'info, TokenAccount>>,
+
+    pub share_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Share authority PDA for `user_nft_mint`
+    #[account(seeds = [USER_SHARES_SEED, user_nft_mint.key().as_ref()], bump)]
+    pub user_share_pda: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = share_mint,
+        associated_token::authority = user_share_pda
+    )]
+    pub user_share_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserInfo::INIT_SPACE,
+        seeds = [USER_INFO_SEED, user_nft_mint.key().as_ref(), user_share_token.key().as_ref()],
+        bump
+    )]
+    pub user_info: Box<Account<'info, UserInfo>>,
+
+    pub nft_program: Program<'info, UniqueLow>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
 }
 
-impl TryFrom<u8> for LockTier {
-    type Error = anchor_lang::error::Error;
+#[derive(Accounts)]
+pub struct MergePositions<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mut, has_one = share_mint)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(address = vault.nft_collection_address @ ErrorCode::InvalidNftCollection)]
+    pub nft_collection: Account<'info, Collection>,
+
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(token::mint = vault.asset_mint, token::authority = vault)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    // Position A, which receives the shares
+    #[account(
+        constraint = target_nft_token.owner == user.key(),
+        constraint = target_nft_token.amount > 0,
+        constraint = target_nft_token.mint == target_nft_mint.key(),
+    )]
+    pub target_nft_token: Account<'info, TokenAccount>,
+
+    pub target_nft_mint: Account<'info, Mint>,
+
+    /// CHECK: Metaplex metadata of `target_nft_mint`; it must carry the vault's
+    /// collection as a verified collection
+    #[account(
+        seeds = [b"metadata", Metadata::id().as_ref(), target_nft_mint.key().as_ref()],
+        bump,
+        seeds::program = Metadata::id(),
+        constraint = is_verified_collection_member(&target_nft_metadata, &vault.nft_collection_mint) @ ErrorCode::NftNotInCollection,
+    )]
+    pub target_nft_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: unique_low's escrow for `target_nft_mint`, usually absent
+    #[account(
+        seeds = [
+            vault.nft_collection_address.as_ref(),
+            token_program.key().as_ref(),
+            target_nft_mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = associated_token_program.key(),
+        constraint = !is_bridged_out(&target_nft_escrow) @ ErrorCode::PositionBridgedOut,
+    )]
+    pub target_nft_escrow: UncheckedAccount<'info>,
+
+    /// CHECK: Share authority PDA for `target_nft_mint`, only used to derive target_share_token
+    #[account(seeds = [USER_SHARES_SEED, target_nft_mint.key().as_ref()], bump)]
+    pub target_share_pda: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = share_mint,
+        associated_token::authority = target_share_pda
+    )]
+    pub target_share_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [USER_INFO_SEED, target_nft_mint.key().as_ref(), target_share_token.key().as_ref()],
+        bump
+    )]
+    pub target_user_info: Account<'info, UserInfo>,
+
+    // Position B, which is emptied and closed
+    #[account(
+        constraint = source_nft_token.owner == user.key(),
+        constraint = source_nft_token.amount > 0,
+        constraint = source_nft_token.mint == source_nft_mint.key(),
+    )]
+    pub source_nft_token: Account<'info, TokenAccount>,
+
+    #[account(
+        constraint = source_nft_mint.key() != target_nft_mint.key() @ ErrorCode::CannotMergeIntoSelf,
+    )]
+    pub source_nft_mint: Account<'info, Mint>,
+
+    /// CHECK: Metaplex metadata of `source_nft_mint`; it must carry the vault's
+    /// collection as a verified collection
+    #[account(
+        seeds = [b"metadata", Metadata::id().as_ref(), source_nft_mint.key().as_ref()],
+        bump,
+        seeds::program = Metadata::id(),
+        constraint = is_verified_collection_member(&source_nft_metadata, &vault.nft_collection_mint) @ ErrorCode::NftNotInCollection,
+    )]
+    pub source_nft_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: unique_low's escrow for `source_nft_mint`, usually absent
+    #[account(
+        seeds = [
+            vault.nft_collection_address.as_ref(),
+            token_program.key().as_ref(),
+            source_nft_mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = associated_token_program.key(),
+        constraint = !is_bridged_out(&source_nft_escrow) @ ErrorCode::PositionBridgedOut,
+    )]
+    pub source_nft_escrow: UncheckedAccount<'info>,
+
+    /// CHECK: Share authority PDA for `source_nft_mint`, signs the share transfer
+    #[account(seeds = [USER_SHARES_SEED, source_nft_mint.key().as_ref()], bump)]
+    pub source_share_pda: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = share_mint,
+        associated_token::authority = source_share_pda
+    )]
+    pub source_share_token: Account<'info, TokenAccount>,
 
-    fn try_from(value: u8) -> Result<Self> {
-        match value {
-            0 => Ok(LockTier::Unlocked),
-            1 => Ok(LockTier::Short),
-            2 => Ok(LockTier::Long),
-            3 => Ok(LockTier::VeryLong),
-            _ => Err(ErrorCode::InvalidLockTier.into()),
-        }
-    }
-}
+    #[account(
+        mut,
+        close = user,
+        seeds = [USER_INFO_SEED, source_nft_mint.key().as_ref(), source_share_token.key().as_ref()],
+        bump
+    )]
+    pub source_user_info: Account<'info, UserInfo>,
 
-#[account]
-pub struct UserInfo {
-    pub vault: Pubkey,
-    pub nft_mint: Pubkey,
-    pub deposit_amount: u64,
-    pub shares: u64,
-    pub locked_until: i64,
-    pub lock_tier: LockTier,
-    pub deposit_time: i64,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 #[derive(Accounts)]
-#[instruction(amount: u64)]
-pub struct Deposit<'info> {
+pub struct SplitPosition<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
 
-    #[account(mut)]
+    #[account(mut, has_one = share_mint)]
     pub vault: Account<'info, Vault>,
 
-    /// The NFT collection account (PDA from NFT program)
+    #[account(token::mint = vault.asset_mint, token::authority = vault)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut, address = vault.nft_collection_address @ ErrorCode::InvalidNftCollection)]
     pub nft_collection: Account<'info, Collection>,
 
-    /// ✅ Must own at least 1 NFT where the mint authority is the collection
+    pub share_mint: Account<'info, Mint>,
+
     #[account(
-        constraint = user_nft_token.owner == user.key(),
-        constraint = user_nft_token.amount > 0,
-        constraint = user_nft_mint.mint_authority == COption::Some(vault.nft_collection_address),
+        constraint = source_nft_token.owner == user.key(),
+        constraint = source_nft_token.amount > 0,
+        constraint = source_nft_token.mint == source_nft_mint.key(),
     )]
-    pub user_nft_token: Account<'info, TokenAccount>,
+    pub source_nft_token: Account<'info, TokenAccount>,
+
+    pub source_nft_mint: Account<'info, Mint>,
 
+    /// CHECK: Metaplex metadata of `source_nft_mint`; it must carry the vault's
+    /// collection as a verified collection
     #[account(
-        constraint = user_nft_token.mint == user_nft_mint.key(),
+        seeds = [b"metadata", Metadata::id().as_ref(), source_nft_mint.key().as_ref()],
+        bump,
+        seeds::program = Metadata::id(),
+        constraint = is_verified_collection_member(&source_nft_metadata, &vault.nft_collection_mint) @ ErrorCode::NftNotInCollection,
     )]
-    pub user_nft_mint: Account<'info, Mint>,
+    pub source_nft_metadata: UncheckedAccount<'info>,
 
-    #[account(mut)]
-    pub asset_mint: Account<'info, Mint>,
+    /// CHECK: unique_low's escrow for `source_nft_mint`, usually absent
+    #[account(
+        seeds = [
+            vault.nft_collection_address.as_ref(),
+            token_program.key().as_ref(),
+            source_nft_mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = associated_token_program.key(),
+        constraint = !is_bridged_out(&nft_escrow) @ ErrorCode::PositionBridgedOut,
+    )]
+    pub nft_escrow: UncheckedAccount<'info>,
+
+    /// CHECK: Share authority PDA for `source_nft_mint`, signs the share transfer
+    #[account(seeds = [USER_SHARES_SEED, source_nft_mint.key().as_ref()], bump)]
+    pub source_share_pda: AccountInfo<'info>,
 
     #[account(
-        init_if_needed,
-        payer = user,
-        associated_token::mint = asset_mint,
-        associated_token::authority = user
+        mut,
+        associated_token::mint = share_mint,
+        associated_token::authority = source_share_pda
     )]
-    pub user_asset_token: Account<'info, TokenAccount>,
+    pub source_share_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [USER_INFO_SEED, source_nft_mint.key().as_ref(), source_share_token.key().as_ref()],
+        bump
+    )]
+    pub source_user_info: Account<'info, UserInfo>,
 
+    /// CHECK: unique_low per-user nonce account, validated by the NFT program
     #[account(mut)]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    pub nft_user_state: UncheckedAccount<'info>,
 
+    /// Fresh keypair; initialised as a mint by the NFT program
     #[account(mut)]
-    pub share_mint: Account<'info, Mint>,
+    pub new_nft_mint: Signer<'info>,
 
-    /// CHECK: This PDA is derived deterministically from user_nft_mint and used as authority for user_share_token.
-    /// It's safe because: 1) Seeds are deterministic, 2) Only used as token account authority, 3) No data stored in this account
-    #[account(
-    seeds = [USER_SHARES_SEED, user_nft_mint.key().as_ref()],
-    bump
-    )]
-    pub user_share_pda: AccountInfo<'info>,
+    /// CHECK: The signer's ATA for `new_nft_mint`, created by the NFT program
+    #[account(mut)]
+    pub new_nft_token: UncheckedAccount<'info>,
+
+    /// CHECK: Registry records for the new NFT, created by the NFT program
+    #[account(mut)]
+    pub new_unique_id_record: UncheckedAccount<'info>,
+
+    /// CHECK: See `new_unique_id_record`
+    #[account(mut)]
+    pub new_token_id_record: UncheckedAccount<'info>,
+
+    /// CHECK: See `new_unique_id_record`
+    #[account(mut)]
+    pub new_mint_record: UncheckedAccount<'info>,
+
+    /// CHECK: Metaplex metadata for `new_nft_mint`, created by the NFT program
+    #[account(mut)]
+    pub new_nft_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Metaplex master edition for `new_nft_mint`, created by the NFT program
+    #[account(mut)]
+    pub new_nft_master_edition: UncheckedAccount<'info>,
+
+    /// CHECK: The collection NFT the new position is verified into
+    #[account(address = vault.nft_collection_mint @ ErrorCode::InvalidNftCollection)]
+    pub nft_collection_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Collection NFT metadata, validated by the NFT program
+    #[account(mut)]
+    pub nft_collection_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Collection NFT master edition, validated by the NFT program
+    pub nft_collection_master_edition: UncheckedAccount<'info>,
+
+    /// CHECK: Share authority PDA for `new_nft_mint`
+    #[account(seeds = [USER_SHARES_SEED, new_nft_mint.key().as_ref()], bump)]
+    pub new_share_pda: AccountInfo<'info>,
 
     #[account(
-    init_if_needed,
-    payer = user,
-    associated_token::mint = share_mint,
-    associated_token::authority = user_share_pda
-)]
-    pub user_share_token: Account<'info, TokenAccount>,
+        init,
+        payer = user,
+        associated_token::mint = share_mint,
+        associated_token::authority = new_share_pda
+    )]
+    pub new_share_token: Account<'info, TokenAccount>,
 
     #[account(
-        init_if_needed,
+        init,
         payer = user,
-        space = 8 + std::mem::size_of::<UserInfo>(),
-        seeds = [USER_INFO_SEED, user_nft_mint.key().as_ref(), user_share_token.key().as_ref()],
+        space = 8 + UserInfo::INIT_SPACE,
+        seeds = [USER_INFO_SEED, new_nft_mint.key().as_ref(), new_share_token.key().as_ref()],
         bump
     )]
-    pub user_info: Account<'info, UserInfo>,
+    pub new_user_info: Account<'info, UserInfo>,
 
+    pub nft_program: Program<'info, UniqueLow>,
     pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
+    pub metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-#[instruction(amount: u64)]
-pub struct Lock<'info> {
+pub struct ClosePosition<'info> {
+    /// Current NFT holder, receives the reclaimed rent
     #[account(mut)]
     pub user: Signer<'info>,
 
-    #[account(mut)]
+    #[account(mut, has_one = share_mint)]
     pub vault: Account<'info, Vault>,
 
-    /// The NFT collection account (PDA from NFT program)
+    #[account(address = vault.nft_collection_address @ ErrorCode::InvalidNftCollection)]
     pub nft_collection: Account<'info, Collection>,
 
-    /// ✅ Must own at least 1 NFT where the mint authority is the collection
     #[account(
         constraint = user_nft_token.owner == user.key(),
         constraint = user_nft_token.amount > 0,
-        constraint = user_nft_mint.mint_authority == COption::Some(vault.nft_collection_address),
+        constraint = user_nft_token.mint == user_nft_mint.key(),
     )]
     pub user_nft_token: Account<'info, TokenAccount>,
 
-    #[account(
-        constraint = user_nft_token.mint == user_nft_mint.key(),
-    )]
     pub user_nft_mint: Account<'info, Mint>,
 
-    #[account(mut)]
-    pub asset_mint: Account<'info, Mint>,
-
+    /// CHECK: Metaplex metadata of `user_nft_mint`; it must carry the vault's
+    /// collection as a verified collection
     #[account(
-        init_if_needed,
-        payer = user,
-        associated_token::mint = asset_mint,
-        associated_token::authority = user
+        seeds = [b"metadata", Metadata::id().as_ref(), user_nft_mint.key().as_ref()],
+        bump,
+        seeds::program = Metadata::id(),
+        constraint = is_verified_collection_member(&user_nft_metadata, &vault.nft_collection_mint) @ ErrorCode::NftNotInCollection,
     )]
-    pub user_asset_token: Account<'info, TokenAccount>,
+    pub user_nft_metadata: UncheckedAccount<'info>,
 
-    #[account(mut)]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    /// CHECK: unique_low's escrow for `user_nft_mint`, usually absent
+    #[account(
+        seeds = [
+            vault.nft_collection_address.as_ref(),
+            token_program.key().as_ref(),
+            user_nft_mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = associated_token_program.key(),
+        constraint = !is_bridged_out(&nft_escrow) @ ErrorCode::PositionBridgedOut,
+    )]
+    pub nft_escrow: UncheckedAccount<'info>,
 
     #[account(mut)]
     pub share_mint: Account<'info, Mint>,
 
-    /// CHECK: This PDA is derived deterministically from user_nft_mint and used as authority for user_share_token.
-    /// It's safe because: 1) Seeds are deterministic, 2) Only used as token account authority, 3) No data stored in this account
-    #[account(
-    seeds = [USER_SHARES_SEED, user_nft_mint.key().as_ref()],
-    bump
-    )]
+    /// CHECK: Share authority PDA for `user_nft_mint`, signs the burn and the
+    /// account close
+    #[account(seeds = [USER_SHARES_SEED, user_nft_mint.key().as_ref()], bump)]
     pub user_share_pda: AccountInfo<'info>,
 
     #[account(
-    init_if_needed,
-    payer = user,
-    associated_token::mint = share_mint,
-    associated_token::authority = user_share_pda
-)]
+        mut,
+        associated_token::mint = share_mint,
+        associated_token::authority = user_share_pda
+    )]
     pub user_share_token: Account<'info, TokenAccount>,
 
     #[account(
-        init_if_needed,
-        payer = user,
-        space = 8 + std::mem::size_of::<UserInfo>(),
+        mut,
+        close = user,
         seeds = [USER_INFO_SEED, user_nft_mint.key().as_ref(), user_share_token.key().as_ref()],
         bump
     )]
     pub user_info: Account<'info, UserInfo>,
 
     pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 #[derive(Accounts)]
-#[instruction(shares: u64)]
-pub struct Withdraw<'info> {
+pub struct MigrateVault<'info> {
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub owner: Signer<'info>,
+
+    /// CHECK: May still have a pre-versioning layout that does not deserialize;
+    /// discriminator and owner are checked in the handler
+    #[account(mut, owner = crate::ID)]
+    pub vault: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateUserInfo<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
 
     #[account(mut)]
     pub vault: Account<'info, Vault>,
 
-    pub nft_collection: Account<'info, Collection>,
+    /// CHECK: Only used as a seed of `user_info`
+    pub user_nft_mint: UncheckedAccount<'info>,
 
+    #[account(token::mint = vault.share_mint)]
+    pub user_share_token: Account<'info, TokenAccount>,
+
+    /// CHECK: May still have a pre-versioning layout that does not deserialize;
+    /// the discriminator is checked in the handler
     #[account(
-        constraint = user_nft_token.owner == user.key(),
-        constraint = user_nft_token.amount > 0,
-        constraint = user_nft_mint.mint_authority == COption::Some(vault.nft_collection_address), // ← Key check!
+        mut,
+        owner = crate::ID,
+        seeds = [USER_INFO_SEED, user_nft_mint.key().as_ref(), user_share_token.key().as_ref()],
+        bump
     )]
-    pub user_nft_token: Account<'info, TokenAccount>,
+    pub user_info: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetLimits<'info> {
+    #[account(mut, has_one = owner)]
+    pub vault: Account<'info, Vault>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetFees<'info> {
+    #[account(mut, has_one = owner)]
+    pub vault: Account<'info, Vault>,
+
+    pub owner: Signer<'info>,
 
     #[account(
-        constraint = user_nft_token.mint == user_nft_mint.key(),
+        associated_token::mint = vault.asset_mint,
+        associated_token::authority = vault,
     )]
-    pub user_nft_mint: Account<'info, Mint>,
+    pub vault_token_account: Account<'info, TokenAccount>,
+}
 
+#[derive(Accounts)]
+pub struct AccrueFees<'info> {
     #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, has_one = asset_mint, has_one = share_mint, has_one = fee_recipient)]
+    pub vault: Account<'info, Vault>,
+
     pub asset_mint: Account<'info, Mint>,
 
     #[account(
-        init_if_needed,
-        payer = user,
         associated_token::mint = asset_mint,
-        associated_token::authority = user
+        associated_token::authority = vault,
     )]
-    pub user_asset_token: Account<'info, TokenAccount>,
-
-    #[account(mut)]
     pub vault_token_account: Account<'info, TokenAccount>,
 
     #[account(mut)]
     pub share_mint: Account<'info, Mint>,
 
-    /// CHECK: This PDA is derived deterministically from user_nft_mint and used as authority for user_share_token.
-    /// It's safe because: 1) Seeds are deterministic, 2) Only used as token account authority, 3) No data stored in this account
-    #[account(
-    seeds = [USER_SHARES_SEED, user_nft_mint.key().as_ref()],
-    bump
-    )]
-    pub user_share_pda: AccountInfo<'info>,
+    /// CHECK: Matched against `vault.fee_recipient`, only used as token account authority
+    pub fee_recipient: UncheckedAccount<'info>,
 
     #[account(
         init_if_needed,
-        payer = user,
+        payer = payer,
         associated_token::mint = share_mint,
-        associated_token::authority = user_share_pda
+        associated_token::authority = fee_recipient
     )]
-    pub user_share_token: Account<'info, TokenAccount>,
+    pub fee_recipient_share_token: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct SetRiskManager<'info> {
+    #[account(mut, has_one = owner)]
+    pub vault: Account<'info, Vault>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddStrategy<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut, has_one = owner)]
+    pub vault: Account<'info, Vault>,
 
     #[account(
-        init_if_needed,
-        payer = user,
-        space = 8 + std::mem::size_of::<UserInfo>(),
-        seeds = [USER_INFO_SEED, user_nft_mint.key().as_ref(), user_share_token.key().as_ref()],
+        init,
+        payer = owner,
+        space = 8 + Strategy::INIT_SPACE,
+        seeds = [STRATEGY_SEED, vault.key().as_ref(), strategy_program.key().as_ref()],
         bump
     )]
-    pub user_info: Account<'info, UserInfo>,
+    pub strategy: Account<'info, Strategy>,
+
+    /// CHECK: Strategy program implementing the adapter interface in `strategy.rs`
+    #[account(executable)]
+    pub strategy_program: UncheckedAccount<'info>,
 
-    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
-pub fn get_lock_duration(tier: &LockTier) -> i64 {
-    match tier {
-        LockTier::Unlocked => 0,
-        LockTier::Short => 30 * 24 * 60 * 60,
-        LockTier::Long => 6 * 30 * 24 * 60 * 60,
-        LockTier::VeryLong => 12 * 30 * 24 * 60 * 60,
-    }
+#[derive(Accounts)]
+pub struct UpdateStrategy<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut, has_one = owner)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(mut, has_one = vault)]
+    pub strategy: Account<'info, Strategy>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveStrategy<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut, has_one = owner)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(mut, close = owner, has_one = vault)]
+    pub strategy: Account<'info, Strategy>,
 }
 
-pub fn calculate_ratio_based_duration(
-    existing_amount: u64,
-    new_amount: u64,
-    full_duration: i64,
-) -> Result<i64> {
-    require!(existing_amount > 0, ErrorCode::InvalidDepositAmount);
+#[derive(Accounts)]
+pub struct StrategyAllocation<'info> {
+    pub risk_manager: Signer<'info>,
+
+    #[account(mut, has_one = risk_manager, has_one = asset_mint)]
+    pub vault: Account<'info, Vault>,
+
+    pub asset_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = asset_mint,
+        associated_token::authority = vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
 
-    // Calculate ratio with scaling for precision
-    let ratio = ((new_amount as u128 * SCALE_U128) / existing_amount as u128) as u64;
+    #[account(
+        mut,
+        has_one = vault,
+        has_one = strategy_program,
+        has_one = strategy_state,
+        has_one = strategy_token_account,
+    )]
+    pub strategy: Account<'info, Strategy>,
 
-    // Calculate duration
-    let duration = ((full_duration as u128 * ratio as u128) / SCALE_U128) as i64;
+    /// CHECK: Matched against `strategy.strategy_program`
+    pub strategy_program: UncheckedAccount<'info>,
 
-    // Apply bounds: min 1 day, max full duration
-    let bounded_duration = duration
-        .max(24 * 60 * 60) // 1 day minimum
-        .min(full_duration); // cap at full duration
+    /// CHECK: Matched against `strategy.strategy_state`, validated by the strategy program
+    pub strategy_state: UncheckedAccount<'info>,
+
+    /// CHECK: Matched against `strategy.strategy_token_account`, validated by the strategy program
+    #[account(mut)]
+    pub strategy_token_account: UncheckedAccount<'info>,
 
-    Ok(bounded_duration)
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct CloseVault<'info> {
+pub struct StrategyReport<'info> {
     #[account(
-        mut,
-        close = authority,
-        seeds = [VAULT_SEED, asset_mint.key().as_ref(), authority.key().as_ref()],
-        bump = vault.bump
+        constraint = reporter.key() == vault.risk_manager
+            || reporter.key() == vault.owner @ ErrorCode::Unauthorized
     )]
+    pub reporter: Signer<'info>,
+
+    #[account(mut)]
     pub vault: Account<'info, Vault>,
 
-    pub asset_mint: Account<'info, Mint>,
+    #[account(
+        associated_token::mint = vault.asset_mint,
+        associated_token::authority = vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        has_one = vault,
+        has_one = strategy_program,
+        has_one = strategy_state,
+        has_one = strategy_token_account,
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    /// CHECK: Matched against `strategy.strategy_program`
+    pub strategy_program: UncheckedAccount<'info>,
+
+    /// CHECK: Matched against `strategy.strategy_state`, validated by the strategy program
+    pub strategy_state: UncheckedAccount<'info>,
+
+    /// CHECK: Matched against `strategy.strategy_token_account`, validated by the strategy program
+    pub strategy_token_account: UncheckedAccount<'info>,
 }
 
 #[error_code]
@@ -801,27 +4718,461 @@ pub enum ErrorCode {
 
     #[msg("Insufficent deposit amount")]
     InvalidDepositAmount,
+
+    #[msg("Maximum number of strategies reached")]
+    StrategyLimitReached,
+
+    #[msg("Strategy debt ceiling exceeded")]
+    DebtCeilingExceeded,
+
+    #[msg("Strategy does not hold that much vault debt")]
+    InsufficientStrategyDebt,
+
+    #[msg("Strategy still holds vault assets")]
+    StrategyHasDebt,
+
+    #[msg("Strategy returned an invalid report")]
+    InvalidStrategyReport,
+
+    #[msg("Withdrawal request is not pending")]
+    RequestNotPending,
+
+    #[msg("Fee exceeds the allowed maximum")]
+    FeeTooHigh,
+
+    #[msg("Accrued fee shares must be minted to the current fee recipient first")]
+    FeesNotClaimed,
+
+    #[msg("Vault deposit cap exceeded")]
+    DepositCapExceeded,
+
+    #[msg("Position size limit exceeded for this NFT")]
+    PositionLimitExceeded,
+
+    #[msg("Withdrawal limit for this epoch exceeded")]
+    EpochWithdrawalLimitExceeded,
+
+    #[msg("Locked TVL cap for this tier exceeded")]
+    TierCapExceeded,
+
+    #[msg("Wallet is not on the vault allowlist")]
+    NotAllowlisted,
+
+    #[msg("NFT is not registered in the vault's collection")]
+    NftNotInCollection,
+
+    #[msg("NFT mint must have a supply of 1 and 0 decimals")]
+    NotUniqueNft,
+
+    #[msg("Signer is not the NFT holder or an approved operator for this action")]
+    UnauthorizedOperator,
+
+    #[msg("Operator scopes must be a non-empty subset of the known scopes")]
+    InvalidOperatorScope,
+
+    #[msg("Operator approval expiry must be in the future")]
+    InvalidOperatorExpiry,
+
+    #[msg("A running lock cannot be moved to a shorter tier")]
+    CannotShortenLock,
+
+    #[msg("Position has no gain to harvest")]
+    NothingToHarvest,
+
+    #[msg("Position still holds shares")]
+    PositionNotEmpty,
+
+    #[msg("Account data does not match the expected type")]
+    InvalidAccountLayout,
+
+    #[msg("Signer is not allowed to perform this action")]
+    Unauthorized,
+
+    #[msg("Account is already at the current version")]
+    AlreadyMigrated,
+
+    #[msg("Position is frozen while its NFT is bridged to another chain")]
+    PositionBridgedOut,
+
+    #[msg("Message does not carry this escrowed position")]
+    InvalidPositionPayload,
+
+    #[msg("A position cannot be merged into itself")]
+    CannotMergeIntoSelf,
+}
+
+#[event]
+pub struct WithdrawEvent {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub user: Pubkey,
+    pub recipient: Pubkey,
+    pub shares: u64,
+    pub amount: u64,
+    pub share_price: u64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct EarlyWithdrawal {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub penalty: u64,
+    pub time_remaining: i64,
+    pub share_price: u64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct VaultInitialized {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub asset_mint: Pubkey,
+    pub share_mint: Pubkey,
+    pub nft_collection: Pubkey,
+    pub share_price: u64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct Deposited {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub shares: u64,
+    pub position_shares: u64,
+    pub share_price: u64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct Locked {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub user: Pubkey,
+    pub tier: u8,
+    pub amount: u64,
+    pub fee: u64,
+    pub shares: u64,
+    pub position_shares: u64,
+    pub old_locked_until: i64,
+    pub new_locked_until: i64,
+    pub share_price: u64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct FeeCharged {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub fee_bps: u64,
+    pub fee: u64,
+    pub share_price: u64,
+    pub seq: u64,
+}
+
+#[derive(Clone, Copy, AnchorDeserialize, AnchorSerialize, PartialEq)]
+pub enum ReserveChangeReason {
+    DepositFee,
+    EarlyWithdrawalPenalty,
+}
+
+#[event]
+pub struct ReservesChanged {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub reason: ReserveChangeReason,
+    /// Amount added to reserves
+    pub amount: u64,
+    pub total_reserves: u64,
+    pub share_price: u64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct WithdrawalRequested {
+    pub vault: Pubkey,
+    pub request_id: u64,
+    pub nft_mint: Pubkey,
+    pub owner: Pubkey,
+    pub shares: u64,
+    pub assets: u64,
+    pub share_price: u64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct WithdrawalCancelled {
+    pub vault: Pubkey,
+    pub request_id: u64,
+    pub nft_mint: Pubkey,
+    /// Shares re-minted for the request's assets
+    pub shares: u64,
+    pub assets: u64,
+    pub share_price: u64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct WithdrawalFulfilled {
+    pub vault: Pubkey,
+    pub request_id: u64,
+    pub nft_mint: Pubkey,
+    pub recipient: Pubkey,
+    pub shares: u64,
+    pub amount: u64,
+    pub share_price: u64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct WithdrawalSkipped {
+    pub vault: Pubkey,
+    pub request_id: u64,
+    pub nft_mint: Pubkey,
+    pub queue_head: u64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct DepositedFor {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub shares: u64,
+    pub share_price: u64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub vault: Pubkey,
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub seq: u64,
+}
+
+#[event]
+pub struct PositionBridged {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub shares: u64,
+    pub assets: u64,
+    pub target_chain_id: u16,
+    pub seq: u64,
+}
+
+#[event]
+pub struct PositionReturned {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub shares: u64,
+    pub locked_until: i64,
+    pub emitter_chain: u16,
+    pub sequence: u64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct PositionReclaimed {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub holder: Pubkey,
+    pub shares: u64,
+    pub locked_until: i64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct PositionClosed {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub holder: Pubkey,
+    pub seq: u64,
+}
+
+#[event]
+pub struct PositionsMerged {
+    pub vault: Pubkey,
+    pub target_nft_mint: Pubkey,
+    pub source_nft_mint: Pubkey,
+    pub shares: u64,
+    pub locked_until: i64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct PositionSplit {
+    pub vault: Pubkey,
+    pub source_nft_mint: Pubkey,
+    pub new_nft_mint: Pubkey,
+    pub shares: u64,
+    pub deposit_amount: u64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct OperatorApproved {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub approver: Pubkey,
+    pub operator: Pubkey,
+    pub scopes: u8,
+    pub expiry: i64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct OperatorRevoked {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub approver: Pubkey,
+    pub operator: Pubkey,
+    pub seq: u64,
+}
+
+#[event]
+pub struct PositionRelocked {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub caller: Pubkey,
+    pub tier: u8,
+    pub locked_until: i64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct Harvested {
+    pub vault: Pubkey,
+    pub nft_mint: Pubkey,
+    pub caller: Pubkey,
+    pub holder: Pubkey,
+    pub shares: u64,
+    pub amount: u64,
+    pub share_price: u64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct AllowlistUpdated {
+    pub vault: Pubkey,
+    pub wallet: Pubkey,
+    pub allowed: bool,
+    pub seq: u64,
+}
+
+#[event]
+pub struct ComplianceUpdated {
+    pub vault: Pubkey,
+    pub compliance_authority: Pubkey,
+    pub allowlist_enabled: bool,
+    pub seq: u64,
+}
+
+#[event]
+pub struct LimitsUpdated {
+    pub vault: Pubkey,
+    pub limits: VaultLimits,
+    pub seq: u64,
+}
+
+#[event]
+pub struct FeesUpdated {
+    pub vault: Pubkey,
+    pub management_fee_bps: u64,
+    pub performance_fee_bps: u64,
+    pub fee_recipient: Pubkey,
+    pub seq: u64,
+}
+
+#[event]
+pub struct FeesAccrued {
+    pub vault: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub management_fee: u64,
+    pub performance_fee: u64,
+    pub fee_shares: u64,
+    pub price_per_share: u64,
+    pub high_water_mark: u64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct FeeSharesMinted {
+    pub vault: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub shares: u64,
+    pub share_price: u64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct RiskManagerUpdated {
+    pub vault: Pubkey,
+    pub risk_manager: Pubkey,
+    pub seq: u64,
+}
+
+#[event]
+pub struct StrategyAdded {
+    pub vault: Pubkey,
+    pub strategy: Pubkey,
+    pub strategy_program: Pubkey,
+    pub debt_ceiling: u64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct StrategyDebtCeilingUpdated {
+    pub vault: Pubkey,
+    pub strategy: Pubkey,
+    pub debt_ceiling: u64,
+    pub seq: u64,
 }
 
 #[event]
-pub struct InterestAccrued {
-    pub total_interest: u64,
-    pub new_index: u64,
+pub struct StrategyRemoved {
+    pub vault: Pubkey,
+    pub strategy: Pubkey,
+    pub seq: u64,
 }
 
 #[event]
-pub struct WithdrawEvent {
-    pub user: Pubkey,
-    pub shares: u64,
+pub struct StrategyAllocated {
+    pub vault: Pubkey,
+    pub strategy: Pubkey,
     pub amount: u64,
+    pub current_debt: u64,
+    pub seq: u64,
 }
 
 #[event]
-pub struct EarlyWithdrawal {
-    pub user: Pubkey,
+pub struct StrategyDeallocated {
+    pub vault: Pubkey,
+    pub strategy: Pubkey,
+    /// Debt written down
     pub amount: u64,
-    pub penalty: u64,
-    pub time_remaining: i64,
+    /// Assets that actually came back; any shortfall is booked as a loss
+    pub received: u64,
+    pub current_debt: u64,
+    pub seq: u64,
+}
+
+#[event]
+pub struct StrategyReported {
+    pub vault: Pubkey,
+    pub strategy: Pubkey,
+    pub gain: u64,
+    pub loss: u64,
+    pub current_debt: u64,
+    pub seq: u64,
 }
 
 #[cfg(test)]
@@ -907,7 +5258,8 @@ mod tests {
         let full_duration = 30 * 24 * 60 * 60; // 30 days
 
         let result =
-            calculate_extension(new_deposit, existing_deposit, time_remaining, full_duration);
+            calculate_extension(new_deposit, existing_deposit, time_remaining, full_duration)
+                .unwrap();
 
         // Should get some extension based on the formula
         assert!(result > 0, "Extension should be positive");
@@ -981,4 +5333,1019 @@ mod tests {
             verylong_net - unlocked_net
         );
     }
+
+    /// Token mint that withholds a fee on every transfer, like a Token-2022
+    /// mint with the transfer-fee extension.
+    struct MockFeeMint {
+        transfer_fee_bps: u64,
+    }
+
+    impl MockFeeMint {
+        fn transfer(&self, vault_balance: u64, amount: u64) -> u64 {
+            let withheld = amount * self.transfer_fee_bps / 10000;
+            vault_balance + amount - withheld
+        }
+    }
+
+    #[test]
+    fn test_received_amount_without_transfer_fee() {
+        let mint = MockFeeMint {
+            transfer_fee_bps: 0,
+        };
+        let before = 500_000_000;
+        let after = mint.transfer(before, 100_000_000);
+
+        assert_eq!(
+            calculate_received_amount(before, after).unwrap(),
+            100_000_000
+        );
+    }
+
+    #[test]
+    fn test_received_amount_with_transfer_fee() {
+        let mint = MockFeeMint {
+            transfer_fee_bps: 200, // 2%
+        };
+        let before = 500_000_000;
+        let after = mint.transfer(before, 100_000_000);
+
+        let received = calculate_received_amount(before, after).unwrap();
+        assert_eq!(received, 98_000_000);
+
+        // Deposit fee is charged on what arrived, not on what was requested
+        let (fee, net) =
+            calculate_deposit_fee(received, get_deposit_fee_bps(&LockTier::Unlocked)).unwrap();
+        assert_eq!(fee, 490_000);
+        assert_eq!(net, 97_510_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid deposit amount")]
+    fn test_received_amount_nothing_arrived() {
+        let mint = MockFeeMint {
+            transfer_fee_bps: 10000,
+        };
+        let before = 500_000_000;
+        let after = mint.transfer(before, 100_000_000);
+
+        calculate_received_amount(before, after).unwrap();
+    }
+
+    #[test]
+    fn test_fee_on_transfer_does_not_dilute_existing_holders() {
+        let mint = MockFeeMint {
+            transfer_fee_bps: 100, // 1%
+        };
+
+        // Existing vault: 1000 tokens backing 1000 shares
+        let total_assets = 1_000_000_000;
+        let total_shares = 1_000_000_000;
+
+        let after = mint.transfer(total_assets, 100_000_000);
+        let received = calculate_received_amount(total_assets, after).unwrap();
+        let (_, net) =
+            calculate_deposit_fee(received, get_deposit_fee_bps(&LockTier::Unlocked)).unwrap();
+        let shares = calculate_shares_to_mint(net, total_shares, total_assets).unwrap();
+
+        // Price per share after the deposit must not drop below 1.0
+        let new_assets = after as u128;
+        let new_shares = (total_shares + shares) as u128;
+        assert!(new_assets >= new_shares, "Deposit diluted existing holders");
+
+        // Pricing on the requested amount would have over-minted
+        let (_, naive_net) =
+            calculate_deposit_fee(100_000_000, get_deposit_fee_bps(&LockTier::Unlocked)).unwrap();
+        let naive_shares = calculate_shares_to_mint(naive_net, total_shares, total_assets).unwrap();
+        assert!(naive_shares > shares);
+    }
+
+    #[test]
+    fn test_token_2022_asset_mint_is_rejected() {
+        use anchor_lang::solana_program::program_pack::Pack;
+        use anchor_spl::token::spl_token::state::Mint as SplMint;
+
+        let key = Pubkey::new_unique();
+        let load = |owner: &Pubkey| {
+            let mut data = vec![0; SplMint::LEN];
+            SplMint {
+                supply: 1_000_000_000,
+                decimals: 6,
+                is_initialized: true,
+                ..Default::default()
+            }
+            .pack_into_slice(&mut data);
+            let mut lamports = 1;
+            let info = AccountInfo::new(
+                &key,
+                false,
+                false,
+                &mut lamports,
+                &mut data,
+                owner,
+                false,
+                0,
+            );
+            Account::<Mint>::try_from(&info).is_ok()
+        };
+
+        assert!(load(&Token::id()));
+        // Same base mint layout a transfer-fee Token-2022 mint starts with
+        assert!(!load(&anchor_spl::token_2022::ID));
+    }
+
+    #[test]
+    fn test_shares_to_mint_empty_vault() {
+        assert_eq!(
+            calculate_shares_to_mint(50_000_000, 0, 0).unwrap(),
+            50_000_000
+        );
+        assert_eq!(
+            calculate_shares_to_mint(50_000_000, 0, 10).unwrap(),
+            50_000_000
+        );
+        assert_eq!(
+            calculate_shares_to_mint(50_000_000, 10, 0).unwrap(),
+            50_000_000
+        );
+    }
+
+    fn test_approval(approver: Pubkey, operator: Pubkey, scopes: u8) -> OperatorApproval {
+        OperatorApproval {
+            vault: Pubkey::new_unique(),
+            nft_mint: Pubkey::new_unique(),
+            approver,
+            operator,
+            scopes,
+            expiry: 1_000,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_holder_needs_no_approval() {
+        let holder = Pubkey::new_unique();
+        assert!(authorize_position(&holder, &holder, None, SCOPE_LOCK, 0).is_ok());
+        assert!(authorize_position(&Pubkey::new_unique(), &holder, None, SCOPE_LOCK, 0).is_err());
+    }
+
+    #[test]
+    fn test_operator_scope_and_expiry() {
+        let holder = Pubkey::new_unique();
+        let operator = Pubkey::new_unique();
+        let approval = test_approval(holder, operator, SCOPE_LOCK | SCOPE_RELOCK);
+
+        assert!(authorize_position(&operator, &holder, Some(&approval), SCOPE_LOCK, 999).is_ok());
+        assert!(authorize_position(&operator, &holder, Some(&approval), SCOPE_RELOCK, 0).is_ok());
+        assert!(authorize_position(&operator, &holder, Some(&approval), SCOPE_HARVEST, 0).is_err());
+        // Expired
+        assert!(
+            authorize_position(&operator, &holder, Some(&approval), SCOPE_LOCK, 1_000).is_err()
+        );
+        // Someone else presenting the same approval
+        let other = Pubkey::new_unique();
+        assert!(authorize_position(&other, &holder, Some(&approval), SCOPE_LOCK, 0).is_err());
+    }
+
+    #[test]
+    fn test_operator_approval_lapses_on_transfer() {
+        let old_holder = Pubkey::new_unique();
+        let new_holder = Pubkey::new_unique();
+        let operator = Pubkey::new_unique();
+        let approval = test_approval(old_holder, operator, SCOPE_ALL);
+
+        assert!(
+            authorize_position(&operator, &new_holder, Some(&approval), SCOPE_LOCK, 0).is_err()
+        );
+    }
+
+    #[test]
+    fn test_harvestable_shares_only_cover_gain() {
+        // 100 shares at 1.5 each, cost basis 100: gain of 50 is 33 shares
+        assert_eq!(
+            calculate_harvestable_shares(100, 100, 1_500, 1_000).unwrap(),
+            33
+        );
+        // At or below cost basis there is nothing to harvest
+        assert_eq!(
+            calculate_harvestable_shares(100, 100, 1_000, 1_000).unwrap(),
+            0
+        );
+        assert_eq!(
+            calculate_harvestable_shares(100, 100, 900, 1_000).unwrap(),
+            0
+        );
+        assert_eq!(calculate_harvestable_shares(100, 100, 0, 0).unwrap(), 0);
+    }
+
+    fn test_position(
+        tier: LockTier,
+        locked_until: i64,
+        deposit_amount: u64,
+        shares: u64,
+    ) -> UserInfo {
+        UserInfo {
+            vault: Pubkey::default(),
+            nft_mint: Pubkey::default(),
+            deposit_amount,
+            shares,
+            locked_until,
+            lock_tier: tier,
+            deposit_time: 0,
+            version: USER_INFO_VERSION,
+            reserved: [0; 32],
+        }
+    }
+
+    #[test]
+    fn test_position_metadata_prices_at_share_price() {
+        let mut vault = test_vault(500);
+        vault.total_shares = 1_000;
+        let position = test_position(LockTier::Short, 2_000, 400, 500);
+
+        // 1_500 idle + 500 deployed backing 1_000 shares: price 2.0
+        let metadata =
+            PositionMetadata::new(Pubkey::default(), &vault, &position, 1_500, 1_000).unwrap();
+        assert_eq!(metadata.assets, 1_000);
+        assert_eq!(metadata.shares, 500);
+        assert_eq!(metadata.deposit_amount, 400);
+        assert_eq!(metadata.share_price, 2 * PRECISION);
+        assert!(metadata.lock_tier == LockTier::Short);
+        assert!(metadata.locked);
+
+        let metadata =
+            PositionMetadata::new(Pubkey::default(), &vault, &position, 1_500, 2_000).unwrap();
+        assert!(!metadata.locked);
+    }
+
+    #[test]
+    fn test_bridged_position_snapshot_round_trips() {
+        let mut vault = test_vault(500);
+        vault.total_shares = 1_000;
+        let vault_key = Pubkey::new_unique();
+        let position = test_position(LockTier::Long, 5_000, 400, 500);
+
+        let snapshot = PositionMetadata::new(vault_key, &vault, &position, 1_500, 1_000)
+            .unwrap()
+            .snapshot();
+        assert_eq!(snapshot.vault, vault_key);
+        assert_eq!(snapshot.shares, 500);
+        assert_eq!(snapshot.assets, 1_000);
+        assert_eq!(snapshot.lock_tier, LockTier::Long as u8);
+        assert_eq!(snapshot.locked_until, 5_000);
+
+        let message = Message::PositionTransfer(unique_low::payload::PositionTransfer {
+            mint: unique_low::payload::MintRequest {
+                recipient: [1; 32],
+                unique_id: [2; 32],
+                target_chain_id: 2,
+            },
+            position: snapshot,
+        });
+        let bytes = message.encode().unwrap();
+        assert_eq!(Message::decode(&bytes).unwrap(), message);
+    }
+
+    #[test]
+    fn test_position_escrow_restores_the_position() {
+        let mut position = test_position(LockTier::Short, 5_000, 400, 500);
+        position.vault = Pubkey::new_unique();
+        position.nft_mint = Pubkey::new_unique();
+        position.deposit_time = 1_000;
+        let escrow = PositionEscrow::new(&position, 2_000, 255);
+
+        // An earlier expiry from the message never shortens the lock
+        let restored = escrow.restore(4_000);
+        assert_eq!(restored.vault, position.vault);
+        assert_eq!(restored.nft_mint, position.nft_mint);
+        assert_eq!(restored.shares, 500);
+        assert_eq!(restored.deposit_amount, 400);
+        assert_eq!(restored.deposit_time, 1_000);
+        assert_eq!(restored.locked_until, 5_000);
+        assert!(restored.lock_tier == LockTier::Short);
+        assert_eq!(restored.version, USER_INFO_VERSION);
+
+        assert_eq!(escrow.restore(9_000).locked_until, 9_000);
+    }
+
+    #[test]
+    fn test_restore_position_recreates_or_merges() {
+        let mut returning = test_position(LockTier::Short, 5_000, 400, 500);
+        returning.vault = Pubkey::new_unique();
+        let mut vault = test_vault(0);
+        vault.book_tier_shares(&LockTier::Short, 500).unwrap();
+
+        // Nothing funded since the NFT came home: the position comes back as is
+        let mut empty = test_position(LockTier::Unlocked, 0, 0, 0);
+        restore_position(&mut vault, &mut empty, &returning, 1_000).unwrap();
+        assert_eq!(empty.shares, 500);
+        assert_eq!(empty.locked_until, 5_000);
+        assert_eq!(vault.total_locked_shares, 500);
+
+        // A fresh unlocked position adopts the returning lock, extended for
+        // the extra deposit
+        let mut funded = test_position(LockTier::Unlocked, 0, 100, 100);
+        funded.vault = returning.vault;
+        restore_position(&mut vault, &mut funded, &returning, 1_000).unwrap();
+        let extension =
+            calculate_extension(100, 400, 4_000, get_lock_duration(&LockTier::Short)).unwrap();
+        assert_eq!(funded.shares, 600);
+        assert_eq!(funded.deposit_amount, 500);
+        assert!(funded.lock_tier == LockTier::Short);
+        assert_eq!(funded.locked_until, 5_000 + extension);
+        assert_eq!(vault.tier_locked_shares[LockTier::Short as usize], 600);
+        assert_eq!(vault.total_locked_shares, 600);
+    }
+
+    #[test]
+    fn test_position_authority_is_this_program() {
+        // unique_low only accepts position snapshots signed under this ID
+        assert_eq!(unique_low::POSITION_VAULT_PROGRAM_ID, crate::ID);
+    }
+
+    #[test]
+    fn test_merge_unlocked_positions_keeps_target_terms() {
+        let target = test_position(LockTier::Unlocked, 0, 100, 100);
+        let source = test_position(LockTier::Unlocked, 0, 50, 50);
+        let (tier, until) = calculate_merged_lock(&target, &source, 1_000).unwrap();
+        assert!(tier == LockTier::Unlocked);
+        assert_eq!(until, 0);
+    }
+
+    #[test]
+    fn test_merge_into_unlocked_adopts_running_lock() {
+        let duration = get_lock_duration(&LockTier::Short);
+        let now = 1_000;
+        let target = test_position(LockTier::Unlocked, 0, 100, 100);
+        let source = test_position(LockTier::Short, now + duration, 100, 100);
+
+        let (tier, until) = calculate_merged_lock(&target, &source, now).unwrap();
+        let extension = calculate_extension(100, 100, duration, duration).unwrap();
+        assert!(tier == LockTier::Short);
+        assert_eq!(until, now + duration + extension);
+        assert!(extension > 0);
+    }
+
+    #[test]
+    fn test_merge_extends_the_later_lock() {
+        let duration = get_lock_duration(&LockTier::Long);
+        let now = 1_000;
+        let target = test_position(LockTier::Long, now + duration / 4, 100, 100);
+        let source = test_position(LockTier::Long, now + duration / 2, 300, 300);
+
+        let (tier, until) = calculate_merged_lock(&target, &source, now).unwrap();
+        assert!(tier == LockTier::Long);
+        assert_eq!(
+            until,
+            now + duration / 2 + calculate_extension(100, 300, duration / 2, duration).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_merge_rejects_mismatched_running_tiers() {
+        let now = 1_000;
+        let target = test_position(LockTier::Short, now + 10, 100, 100);
+        let source = test_position(LockTier::Long, now + 20, 100, 100);
+        assert!(calculate_merged_lock(&target, &source, now).is_err());
+    }
+
+    #[test]
+    fn test_move_tier_shares_books_locked_totals() {
+        let mut vault = test_vault(0);
+        vault
+            .move_tier_shares(&LockTier::Unlocked, &LockTier::Short, 40, 1_000, 1_000)
+            .unwrap();
+        assert_eq!(vault.tier_locked_shares[LockTier::Short as usize], 40);
+        assert_eq!(vault.total_locked_shares, 40);
+
+        vault
+            .move_tier_shares(&LockTier::Short, &LockTier::Long, 40, 1_000, 1_000)
+            .unwrap();
+        assert_eq!(vault.tier_locked_shares, [0, 0, 40, 0]);
+        assert_eq!(vault.total_locked_shares, 40);
+
+        vault.limits.tier_caps[LockTier::VeryLong as usize] = 10;
+        assert!(vault
+            .move_tier_shares(&LockTier::Long, &LockTier::VeryLong, 40, 1_000, 1_000)
+            .is_err());
+
+        // Returning positions are re-booked past the cap
+        vault
+            .rebook_tier_shares(&LockTier::Long, &LockTier::VeryLong, 40)
+            .unwrap();
+        assert_eq!(vault.tier_locked_shares, [0, 0, 0, 40]);
+        assert_eq!(vault.total_locked_shares, 40);
+
+        // Withdrawing from a tier releases the locked total with it
+        vault.release_tier_shares(&LockTier::VeryLong, 15).unwrap();
+        assert_eq!(vault.tier_locked_shares, [0, 0, 0, 25]);
+        assert_eq!(vault.total_locked_shares, 25);
+        vault.release_tier_shares(&LockTier::Unlocked, 15).unwrap();
+        assert_eq!(vault.total_locked_shares, 25);
+
+        vault
+            .move_tier_shares(&LockTier::VeryLong, &LockTier::Unlocked, 25, 1_000, 1_000)
+            .unwrap();
+        assert_eq!(vault.tier_locked_shares, [0; 4]);
+        assert_eq!(vault.total_locked_shares, 0);
+    }
+
+    #[test]
+    fn test_event_seq_is_monotonic() {
+        let mut vault = test_vault(0);
+        assert_eq!(vault.next_event_seq().unwrap(), 1);
+        assert_eq!(vault.next_event_seq().unwrap(), 2);
+        assert_eq!(vault.event_seq, 2);
+    }
+
+    #[test]
+    fn test_init_space_covers_full_accounts() {
+        let mut vault = test_vault(0);
+        vault.strategies = vec![Pubkey::new_unique(); MAX_STRATEGIES];
+        assert_eq!(vault.try_to_vec().unwrap().len(), Vault::INIT_SPACE);
+
+        let position = test_position(LockTier::Long, 1, 2, 3);
+        assert_eq!(position.try_to_vec().unwrap().len(), UserInfo::INIT_SPACE);
+    }
+
+    #[test]
+    fn test_zero_extended_legacy_user_info_reads_as_version_zero() {
+        // Pre-versioning accounts were sized with the Rust layout and hold the
+        // Borsh fields followed by zero padding
+        let legacy = test_position(LockTier::Short, 1_000, 500, 400);
+        let mut data = UserInfo::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&legacy.try_to_vec().unwrap()[..UserInfo::INIT_SPACE - 1 - 32]);
+        data.resize(8 + UserInfo::INIT_SPACE, 0);
+
+        let migrated = UserInfo::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(migrated.version, 0);
+        assert_eq!(migrated.shares, 400);
+        assert_eq!(migrated.deposit_amount, 500);
+        assert_eq!(migrated.locked_until, 1_000);
+        assert!(migrated.lock_tier == LockTier::Short);
+    }
+
+    fn test_vault(total_debt: u64) -> Vault {
+        Vault {
+            owner: Pubkey::default(),
+            asset_mint: Pubkey::default(),
+            share_mint: Pubkey::default(),
+            nft_collection_address: Pubkey::default(),
+            last_update_time: 0,
+            reserve_factor: 0,
+            total_reserves: 0,
+            total_shares: 0,
+            total_locked_shares: 0,
+            total_unlocked_shares: 0,
+            bump: 0,
+            risk_manager: Pubkey::default(),
+            total_debt,
+            strategies: Vec::new(),
+            queue_head: 0,
+            queue_tail: 0,
+            total_queued_assets: 0,
+            fee_recipient: Pubkey::default(),
+            management_fee_bps: 0,
+            performance_fee_bps: 0,
+            high_water_mark: PRECISION,
+            limits: VaultLimits::default(),
+            tier_locked_shares: [0; 4],
+            epoch_start: 0,
+            epoch_withdrawn: 0,
+            compliance_authority: Pubkey::default(),
+            allowlist_enabled: false,
+            version: VAULT_VERSION,
+            event_seq: 0,
+            nft_collection_mint: Pubkey::default(),
+            pending_fee_shares: 0,
+            reserved: [0; 16],
+        }
+    }
+
+    fn test_metadata(collection: Option<(Pubkey, bool)>) -> Vec<u8> {
+        use anchor_spl::metadata::mpl_token_metadata::{accounts, types};
+        accounts::Metadata {
+            key: types::Key::MetadataV1,
+            update_authority: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            name: "Position #1".to_string(),
+            symbol: "POS".to_string(),
+            uri: String::new(),
+            seller_fee_basis_points: 500,
+            creators: None,
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: None,
+            collection: collection.map(|(key, verified)| types::Collection { verified, key }),
+            uses: None,
+            collection_details: None,
+            programmable_config: None,
+        }
+        .try_to_vec()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_verified_collection_member() {
+        let collection_mint = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let metadata_owner = Metadata::id();
+        let system_owner = Pubkey::default();
+        let check = |data: Vec<u8>, owner: &Pubkey| {
+            let mut data = data;
+            let mut lamports = 1;
+            let info = AccountInfo::new(
+                &key,
+                false,
+                false,
+                &mut lamports,
+                &mut data,
+                owner,
+                false,
+                0,
+            );
+            is_verified_collection_member(&info, &collection_mint)
+        };
+
+        assert!(check(
+            test_metadata(Some((collection_mint, true))),
+            &metadata_owner
+        ));
+        // Set but not verified by the collection authority
+        assert!(!check(
+            test_metadata(Some((collection_mint, false))),
+            &metadata_owner
+        ));
+        assert!(!check(
+            test_metadata(Some((Pubkey::new_unique(), true))),
+            &metadata_owner
+        ));
+        assert!(!check(test_metadata(None), &metadata_owner));
+        // Same bytes in an account the metadata program does not own
+        assert!(!check(
+            test_metadata(Some((collection_mint, true))),
+            &system_owner
+        ));
+        assert!(!check(Vec::new(), &metadata_owner));
+    }
+
+    #[test]
+    fn test_bridged_out_escrow() {
+        use anchor_lang::solana_program::program_pack::Pack;
+        use anchor_spl::token::spl_token::state::{Account as SplAccount, AccountState};
+
+        let key = Pubkey::new_unique();
+        let escrow = |amount: u64| {
+            let mut data = vec![0; SplAccount::LEN];
+            SplAccount {
+                mint: Pubkey::new_unique(),
+                owner: Pubkey::new_unique(),
+                amount,
+                state: AccountState::Initialized,
+                ..Default::default()
+            }
+            .pack_into_slice(&mut data);
+            data
+        };
+        let check = |data: Vec<u8>, owner: &Pubkey| {
+            let mut data = data;
+            let mut lamports = 1;
+            let info = AccountInfo::new(
+                &key,
+                false,
+                false,
+                &mut lamports,
+                &mut data,
+                owner,
+                false,
+                0,
+            );
+            is_bridged_out(&info)
+        };
+
+        assert!(check(escrow(1), &Token::id()));
+        // Released back to its holder
+        assert!(!check(escrow(0), &Token::id()));
+        // Never bridged, the escrow was not created
+        assert!(!check(Vec::new(), &Pubkey::default()));
+        // Same bytes in an account the token program does not own
+        assert!(!check(escrow(1), &Pubkey::default()));
+    }
+
+    #[test]
+    fn test_total_assets_includes_strategy_debt() {
+        assert_eq!(
+            get_total_assets(400_000_000, &test_vault(0)).unwrap(),
+            400_000_000
+        );
+        assert_eq!(
+            get_total_assets(400_000_000, &test_vault(600_000_000)).unwrap(),
+            1_000_000_000
+        );
+        assert!(get_total_assets(u64::MAX, &test_vault(1)).is_err());
+    }
+
+    #[test]
+    fn test_allocation_does_not_move_share_price() {
+        // 1000 tokens backing 1000 shares, then 600 allocated to a strategy
+        let total_shares = 1_000_000_000;
+        let before = get_total_assets(1_000_000_000, &test_vault(0)).unwrap();
+        let after = get_total_assets(400_000_000, &test_vault(600_000_000)).unwrap();
+        assert_eq!(before, after);
+
+        let shares_before = calculate_shares_to_mint(10_000_000, total_shares, before).unwrap();
+        let shares_after = calculate_shares_to_mint(10_000_000, total_shares, after).unwrap();
+        assert_eq!(shares_before, shares_after);
+    }
+
+    #[test]
+    fn test_strategy_pnl() {
+        assert_eq!(calculate_strategy_pnl(100, 100), (0, 0));
+        assert_eq!(calculate_strategy_pnl(100, 130), (30, 0));
+        assert_eq!(calculate_strategy_pnl(100, 75), (0, 25));
+        assert_eq!(calculate_strategy_pnl(0, 5), (5, 0));
+    }
+
+    #[test]
+    fn test_adapter_matches_mock_strategy_interface() {
+        use anchor_lang::Discriminator;
+
+        assert_eq!(
+            sighash("deposit").as_slice(),
+            mock_strategy::instruction::Deposit::DISCRIMINATOR
+        );
+        assert_eq!(
+            sighash("withdraw").as_slice(),
+            mock_strategy::instruction::Withdraw::DISCRIMINATOR
+        );
+        assert_eq!(
+            sighash("total_assets").as_slice(),
+            mock_strategy::instruction::TotalAssets::DISCRIMINATOR
+        );
+    }
+
+    #[test]
+    fn test_withdrawal_queue_ids_are_fifo() {
+        let mut vault = test_vault(0);
+
+        assert_eq!(vault.enqueue_withdrawal(100).unwrap(), 0);
+        assert_eq!(vault.enqueue_withdrawal(250).unwrap(), 1);
+        assert_eq!(vault.enqueue_withdrawal(50).unwrap(), 2);
+        assert_eq!(vault.queue_tail, 3);
+        assert_eq!(vault.total_queued_assets, 400);
+
+        // Cancelling releases the assets but keeps the slot for the crank
+        vault.dequeue_assets(250).unwrap();
+        assert_eq!(vault.total_queued_assets, 150);
+        assert_eq!(vault.queue_tail, 3);
+
+        assert!(vault.dequeue_assets(151).is_err());
+    }
+
+    #[test]
+    fn test_queued_withdrawal_priced_at_request() {
+        // 1000 shares over 1000 tokens, 600 of which sit in a strategy
+        let mut vault = test_vault(600_000_000);
+        let total_shares = 1_000_000_000;
+        let requested = 100_000_000;
+
+        let at_request = get_total_assets(400_000_000, &vault).unwrap();
+        let assets = calculate_assets_for_shares(requested, at_request, total_shares).unwrap();
+        assert_eq!(assets, 100_000_000);
+        vault.enqueue_withdrawal(assets).unwrap();
+        let total_shares = total_shares - requested;
+
+        // Strategy reports a 10% gain before liquidity returns: the request
+        // keeps its price and the gain goes to the remaining holders
+        vault.total_debt = 660_000_000;
+        let at_fulfillment = get_total_assets(400_000_000, &vault).unwrap();
+        assert_eq!(at_fulfillment, 960_000_000);
+        assert_eq!(
+            calculate_assets_for_shares(total_shares, at_fulfillment, total_shares).unwrap(),
+            960_000_000
+        );
+    }
+
+    #[test]
+    fn test_withdrawals_leave_queued_liquidity() {
+        let mut vault = test_vault(0);
+        vault.enqueue_withdrawal(100_000_000).unwrap();
+
+        assert!(vault.check_liquidity(400_000_000, 300_000_000).is_ok());
+        assert!(vault.check_liquidity(400_000_000, 300_000_001).is_err());
+        // Queue alone is underfunded
+        assert!(vault.check_liquidity(50_000_000, 1).is_err());
+    }
+
+    #[test]
+    fn test_assets_for_shares_empty_vault() {
+        assert_eq!(calculate_assets_for_shares(100, 1_000, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_no_fees_configured() {
+        let accrual = calculate_fee_accrual(
+            1_000_000_000,
+            1_000_000_000,
+            PRECISION,
+            0,
+            0,
+            SECONDS_PER_YEAR,
+        )
+        .unwrap();
+        assert_eq!(accrual.fee_shares, 0);
+        assert_eq!(accrual.price_per_share, PRECISION);
+    }
+
+    #[test]
+    fn test_management_fee_full_year() {
+        // 2% per year on 1000 tokens, no gains
+        let accrual = calculate_fee_accrual(
+            1_000_000_000,
+            1_000_000_000,
+            PRECISION,
+            200,
+            2000,
+            SECONDS_PER_YEAR,
+        )
+        .unwrap();
+        assert_eq!(accrual.management_fee, 20_000_000);
+        assert_eq!(accrual.performance_fee, 0);
+
+        // Recipient's new shares are worth the fee
+        let recipient_assets = calculate_assets_for_shares(
+            accrual.fee_shares,
+            1_000_000_000,
+            1_000_000_000 + accrual.fee_shares,
+        )
+        .unwrap();
+        assert!(recipient_assets.abs_diff(20_000_000) <= 1);
+        assert_eq!(accrual.high_water_mark, PRECISION);
+    }
+
+    #[test]
+    fn test_performance_fee_above_high_water_mark() {
+        // Price per share went from 1.0 to 1.1, 20% performance fee
+        let accrual =
+            calculate_fee_accrual(1_100_000_000, 1_000_000_000, PRECISION, 0, 2000, 0).unwrap();
+        assert_eq!(accrual.management_fee, 0);
+        assert_eq!(accrual.performance_fee, 20_000_000);
+
+        // Holders keep 80% of the gain, and that becomes the new mark
+        assert!(accrual.price_per_share.abs_diff(1_080_000_000) <= 1);
+        assert_eq!(accrual.high_water_mark, accrual.price_per_share);
+    }
+
+    #[test]
+    fn test_no_performance_fee_below_high_water_mark() {
+        // Recovering from 1.2 back to 1.1 is not profit
+        let accrual =
+            calculate_fee_accrual(1_100_000_000, 1_000_000_000, 1_200_000_000, 0, 2000, 0).unwrap();
+        assert_eq!(accrual.performance_fee, 0);
+        assert_eq!(accrual.fee_shares, 0);
+        assert_eq!(accrual.high_water_mark, 1_200_000_000);
+    }
+
+    #[test]
+    fn test_fee_accrual_empty_vault() {
+        let accrual = calculate_fee_accrual(0, 0, PRECISION, 500, 5000, SECONDS_PER_YEAR).unwrap();
+        assert_eq!(
+            accrual,
+            FeeAccrual {
+                price_per_share: PRECISION,
+                high_water_mark: PRECISION,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_limits_disabled_by_default() {
+        let vault = test_vault(0);
+        vault.check_deposit_cap(u64::MAX).unwrap();
+        vault.check_position_size(u64::MAX, u64::MAX, 1).unwrap();
+        vault
+            .check_tier_cap(&LockTier::VeryLong, u64::MAX / 2, u64::MAX, 1)
+            .unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Vault deposit cap exceeded")]
+    fn test_deposit_cap() {
+        let mut vault = test_vault(0);
+        vault.limits.deposit_cap = 1_000_000_000;
+        vault.check_deposit_cap(1_000_000_000).unwrap();
+        vault.check_deposit_cap(1_000_000_001).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Position size limit exceeded for this NFT")]
+    fn test_position_size_limit() {
+        let mut vault = test_vault(0);
+        vault.limits.max_position_size = 100_000_000;
+
+        // 100 of 1000 shares over 1000 tokens is exactly at the limit
+        vault
+            .check_position_size(100_000_000, 1_000_000_000, 1_000_000_000)
+            .unwrap();
+        // Same shares after the vault doubled in value is over it
+        vault
+            .check_position_size(100_000_000, 2_000_000_000, 1_000_000_000)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_tier_cap_is_per_tier() {
+        let mut vault = test_vault(0);
+        vault.limits.tier_caps = [0, 100_000_000, 0, 0];
+        vault
+            .book_tier_shares(&LockTier::Short, 90_000_000)
+            .unwrap();
+
+        let at_cap =
+            vault.check_tier_cap(&LockTier::Short, 10_000_000, 1_000_000_000, 1_000_000_000);
+        assert!(at_cap.is_ok());
+        let over_cap =
+            vault.check_tier_cap(&LockTier::Short, 10_000_001, 1_000_000_000, 1_000_000_000);
+        assert!(over_cap.is_err());
+        let other_tier =
+            vault.check_tier_cap(&LockTier::Long, 500_000_000, 1_000_000_000, 1_000_000_000);
+        assert!(other_tier.is_ok());
+
+        vault
+            .release_tier_shares(&LockTier::Short, 50_000_000)
+            .unwrap();
+        assert_eq!(
+            vault.tier_locked_shares[LockTier::Short as usize],
+            40_000_000
+        );
+        assert_eq!(vault.total_locked_shares, 40_000_000);
+        // Releasing more than the tier holds means the books are off
+        assert!(vault
+            .release_tier_shares(&LockTier::Short, u64::MAX)
+            .is_err());
+        assert_eq!(
+            vault.tier_locked_shares[LockTier::Short as usize],
+            40_000_000
+        );
+    }
+
+    #[test]
+    fn test_epoch_withdrawal_limit() {
+        let mut vault = test_vault(0);
+        vault.limits.epoch_duration = SECONDS_PER_DAY;
+        vault.limits.epoch_withdrawal_limit = 100_000_000;
+
+        vault.record_withdrawal(60_000_000, 10).unwrap();
+        vault.record_withdrawal(40_000_000, 20).unwrap();
+        assert!(vault.record_withdrawal(1, 30).is_err());
+        assert_eq!(vault.epoch_withdrawn, 100_000_000);
+
+        // Next window starts fresh
+        vault
+            .record_withdrawal(100_000_000, SECONDS_PER_DAY)
+            .unwrap();
+        assert_eq!(vault.epoch_start, SECONDS_PER_DAY);
+        assert_eq!(vault.epoch_withdrawn, 100_000_000);
+    }
+
+    #[test]
+    fn test_withdrawal_size_fits_one_epoch() {
+        let mut vault = test_vault(0);
+        vault.check_withdrawal_size(u64::MAX).unwrap();
+
+        vault.limits.epoch_duration = SECONDS_PER_DAY;
+        vault.limits.epoch_withdrawal_limit = 100_000_000;
+        vault.check_withdrawal_size(100_000_000).unwrap();
+        assert!(vault.check_withdrawal_size(100_000_001).is_err());
+    }
+
+    #[test]
+    fn test_allowlist_gate() {
+        let mut vault = test_vault(0);
+
+        // Disabled: NFT gating alone decides
+        vault.check_allowlist(false).unwrap();
+        vault.check_allowlist(true).unwrap();
+
+        vault.allowlist_enabled = true;
+        vault.check_allowlist(true).unwrap();
+        assert!(vault.check_allowlist(false).is_err());
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        const MAX_DURATION: i64 = 4 * 365 * 24 * 60 * 60;
+
+        proptest! {
+            #[test]
+            fn mul_div_rounding_differs_by_at_most_one(
+                a in any::<u32>(),
+                b in any::<u32>(),
+                d in 1u64..=u64::MAX,
+            ) {
+                let down = mul_div(a as u64, b as u64, d, Rounding::Down).unwrap();
+                let up = mul_div(a as u64, b as u64, d, Rounding::Up).unwrap();
+                prop_assert!(up >= down && up - down <= 1);
+            }
+
+            #[test]
+            fn mul_div_overflow_is_an_error(a in any::<u64>(), b in any::<u64>(), d in 1u64..=u64::MAX) {
+                let exact = a as u128 * b as u128 / d as u128;
+                let result = mul_div(a, b, d, Rounding::Down);
+                prop_assert_eq!(result.is_ok(), exact <= u64::MAX as u128);
+            }
+
+            #[test]
+            fn extension_stays_within_full_duration(
+                new_deposit in any::<u64>(),
+                existing in 1u64..=u64::MAX,
+                time_remaining in any::<i64>(),
+                full_duration in any::<i64>(),
+            ) {
+                let extension =
+                    calculate_extension(new_deposit, existing, time_remaining, full_duration).unwrap();
+                prop_assert!(extension >= 0);
+                prop_assert!(extension <= full_duration.max(0));
+            }
+
+            #[test]
+            fn extension_is_monotonic_in_deposit(
+                smaller in any::<u64>(),
+                larger in any::<u64>(),
+                existing in 1u64..=u64::MAX,
+                time_remaining in 0..=MAX_DURATION,
+                full_duration in 1..=MAX_DURATION,
+            ) {
+                let (smaller, larger) = (smaller.min(larger), smaller.max(larger));
+                let low = calculate_extension(smaller, existing, time_remaining, full_duration).unwrap();
+                let high = calculate_extension(larger, existing, time_remaining, full_duration).unwrap();
+                prop_assert!(low <= high);
+            }
+
+            #[test]
+            fn ratio_duration_stays_within_bounds(
+                existing in 1u64..=u64::MAX,
+                new_amount in any::<u64>(),
+                full_duration in MIN_LOCK_DURATION..=i64::MAX,
+            ) {
+                let duration =
+                    calculate_ratio_based_duration(existing, new_amount, full_duration).unwrap();
+                prop_assert!((MIN_LOCK_DURATION..=full_duration).contains(&duration));
+            }
+
+            #[test]
+            fn ratio_duration_is_monotonic_in_amount(
+                existing in 1u64..=u64::MAX,
+                smaller in any::<u64>(),
+                larger in any::<u64>(),
+                full_duration in MIN_LOCK_DURATION..=MAX_DURATION,
+            ) {
+                let (smaller, larger) = (smaller.min(larger), smaller.max(larger));
+                let low = calculate_ratio_based_duration(existing, smaller, full_duration).unwrap();
+                let high = calculate_ratio_based_duration(existing, larger, full_duration).unwrap();
+                prop_assert!(low <= high);
+            }
+
+            #[test]
+            fn share_round_trip_never_creates_assets(
+                deposit in 0u64..=1_000_000_000_000_000,
+                total_shares in 1u64..=1_000_000_000_000_000,
+                total_assets in 1u64..=1_000_000_000_000_000,
+            ) {
+                let shares = calculate_shares_to_mint(deposit, total_shares, total_assets).unwrap();
+                let assets = calculate_assets_for_shares(
+                    shares,
+                    total_assets + deposit,
+                    total_shares + shares,
+                )
+                .unwrap();
+                prop_assert!(assets <= deposit);
+            }
+
+            #[test]
+            fn share_conversions_are_monotonic(
+                smaller in any::<u64>(),
+                larger in any::<u64>(),
+                total_shares in 1u64..=u64::MAX,
+                total_assets in 1u64..=u64::MAX,
+            ) {
+                let (smaller, larger) = (smaller.min(larger), smaller.max(larger));
+                if let (Ok(low), Ok(high)) = (
+                    calculate_shares_to_mint(smaller, total_shares, total_assets),
+                    calculate_shares_to_mint(larger, total_shares, total_assets),
+                ) {
+                    prop_assert!(low <= high);
+                }
+                if let (Ok(low), Ok(high)) = (
+                    calculate_assets_for_shares(smaller, total_assets, total_shares),
+                    calculate_assets_for_shares(larger, total_assets, total_shares),
+                ) {
+                    prop_assert!(low <= high);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+// ================================
+// FIXED-POINT MATH
+// ================================
+//
+// Every amount, share and duration calculation in the vault goes through
+// these helpers. Products are formed in u128, so `a * b / d` cannot overflow
+// half way; a result that does not fit back into the target type, a negative
+// duration or a zero denominator is `ErrorCode::MathOverflow`.
+//
+// Rounding is always explicit. Round down whatever the vault hands out
+// (shares minted, assets paid, fee shares minted) and up whatever it keeps
+// (deposit fees, penalties), so rounding dust stays with existing holders.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// `a * b / denominator` with the given rounding.
+pub fn mul_div(a: u64, b: u64, denominator: u64, rounding: Rounding) -> Result<u64> {
+    require!(denominator > 0, ErrorCode::MathOverflow);
+    let product = a as u128 * b as u128;
+    let denominator = denominator as u128;
+    let mut quotient = product / denominator;
+    if rounding == Rounding::Up && product % denominator != 0 {
+        quotient += 1;
+    }
+    u64::try_from(quotient).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+/// `a * b / denominator` rounded down, saturating at `u64::MAX` instead of
+/// failing. Only for intermediate ratios that are clamped afterwards.
+pub fn mul_div_saturating(a: u64, b: u64, denominator: u64) -> Result<u64> {
+    require!(denominator > 0, ErrorCode::MathOverflow);
+    let quotient = a as u128 * b as u128 / denominator as u128;
+    Ok(u64::try_from(quotient).unwrap_or(u64::MAX))
+}
+
+pub fn checked_add(a: u64, b: u64) -> Result<u64> {
+    a.checked_add(b).ok_or(error!(ErrorCode::MathOverflow))
+}
+
+pub fn checked_sub(a: u64, b: u64) -> Result<u64> {
+    a.checked_sub(b).ok_or(error!(ErrorCode::MathOverflow))
+}
+
+pub fn checked_add_i64(a: i64, b: i64) -> Result<i64> {
+    a.checked_add(b).ok_or(error!(ErrorCode::MathOverflow))
+}
+
+pub fn checked_sub_i64(a: i64, b: i64) -> Result<i64> {
+    a.checked_sub(b).ok_or(error!(ErrorCode::MathOverflow))
+}
+
+/// Durations and timestamps are i64 on chain; these convert without wrapping.
+pub fn to_u64(value: i64) -> Result<u64> {
+    u64::try_from(value).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+pub fn to_i64(value: u64) -> Result<i64> {
+    i64::try_from(value).map_err(|_| error!(ErrorCode::MathOverflow))
+}
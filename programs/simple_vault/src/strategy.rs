@@ -0,0 +1,149 @@
+use anchor_lang::prelude::*;
+use solana_program::{
+    hash::hash,
+    instruction::{AccountMeta, Instruction},
+    program::{get_return_data, invoke_signed},
+};
+
+use crate::ErrorCode;
+
+// ================================
+// STRATEGY ADAPTER INTERFACE
+// ================================
+//
+// Any program the vault allocates to must expose three Anchor-style
+// instructions taking the accounts below in this order:
+//
+//   deposit(amount: u64)   vault (signer), vault_token_account (mut),
+//                          strategy_state, strategy_token_account (mut), token_program
+//   withdraw(amount: u64)  same accounts as deposit
+//   total_assets() -> u64  strategy_state, strategy_token_account
+//
+// `deposit` pulls `amount` out of the vault token account using the vault's
+// signature, `withdraw` sends `amount` back, and `total_assets` returns the
+// current value of the position through return data.
+
+#[derive(Accounts)]
+pub struct StrategyTransfer<'info> {
+    /// CHECK: Vault PDA, signs the CPI
+    pub vault: AccountInfo<'info>,
+    /// CHECK: Vault asset token account
+    pub vault_token_account: AccountInfo<'info>,
+    /// CHECK: Strategy-owned state account
+    pub strategy_state: AccountInfo<'info>,
+    /// CHECK: Strategy-owned token account
+    pub strategy_token_account: AccountInfo<'info>,
+    /// CHECK: SPL token program
+    pub token_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StrategyValue<'info> {
+    /// CHECK: Strategy-owned state account
+    pub strategy_state: AccountInfo<'info>,
+    /// CHECK: Strategy-owned token account
+    pub strategy_token_account: AccountInfo<'info>,
+}
+
+/// Anchor instruction discriminator: first 8 bytes of sha256("global:<name>").
+pub fn sighash(name: &str) -> [u8; 8] {
+    let preimage = format!("global:{}", name);
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash(preimage.as_bytes()).to_bytes()[..8]);
+    discriminator
+}
+
+pub fn strategy_deposit<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, StrategyTransfer<'info>>,
+    amount: u64,
+) -> Result<()> {
+    invoke_strategy_transfer(ctx, "deposit", amount)
+}
+
+pub fn strategy_withdraw<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, StrategyTransfer<'info>>,
+    amount: u64,
+) -> Result<()> {
+    invoke_strategy_transfer(ctx, "withdraw", amount)
+}
+
+fn invoke_strategy_transfer<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, StrategyTransfer<'info>>,
+    name: &str,
+    amount: u64,
+) -> Result<()> {
+    let mut data = sighash(name).to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    let ix = Instruction {
+        program_id: ctx.program.key(),
+        accounts: vec![
+            AccountMeta::new_readonly(ctx.accounts.vault.key(), true),
+            AccountMeta::new(ctx.accounts.vault_token_account.key(), false),
+            AccountMeta::new_readonly(ctx.accounts.strategy_state.key(), false),
+            AccountMeta::new(ctx.accounts.strategy_token_account.key(), false),
+            AccountMeta::new_readonly(ctx.accounts.token_program.key(), false),
+        ],
+        data,
+    };
+
+    invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.vault.clone(),
+            ctx.accounts.vault_token_account.clone(),
+            ctx.accounts.strategy_state.clone(),
+            ctx.accounts.strategy_token_account.clone(),
+            ctx.accounts.token_program.clone(),
+            ctx.program.clone(),
+        ],
+        ctx.signer_seeds,
+    )?;
+
+    Ok(())
+}
+
+pub fn strategy_total_assets<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, StrategyValue<'info>>,
+) -> Result<u64> {
+    let ix = Instruction {
+        program_id: ctx.program.key(),
+        accounts: vec![
+            AccountMeta::new_readonly(ctx.accounts.strategy_state.key(), false),
+            AccountMeta::new_readonly(ctx.accounts.strategy_token_account.key(), false),
+        ],
+        data: sighash("total_assets").to_vec(),
+    };
+
+    invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.strategy_state.clone(),
+            ctx.accounts.strategy_token_account.clone(),
+            ctx.program.clone(),
+        ],
+        ctx.signer_seeds,
+    )?;
+
+    let (program_id, data) = get_return_data().ok_or(ErrorCode::InvalidStrategyReport)?;
+    require_keys_eq!(
+        program_id,
+        ctx.program.key(),
+        ErrorCode::InvalidStrategyReport
+    );
+    let value: [u8; 8] = data
+        .get(..8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(ErrorCode::InvalidStrategyReport)?;
+
+    Ok(u64::from_le_bytes(value))
+}
+
+/// Splits a strategy's reported value against its recorded debt into (gain, loss).
+pub fn calculate_strategy_pnl(current_debt: u64, reported_value: u64) -> (u64, u64) {
+    if reported_value >= current_debt {
+        (reported_value - current_debt, 0)
+    } else {
+        (0, current_debt - reported_value)
+    }
+}
@@ -9,4 +9,7 @@ pub const MAX_COLLECTION_SIZE: usize = 50;
 pub const SOLANA_CHAIN_ID: u64 = 1; // Solana mainnet chain ID for consistency with Ethereum
 
 // Wormhole constants
-pub const WORMHOLE_CONSISTENCY_LEVEL: u8 = 1; // Same as Ethereum contract
\ No newline at end of file
+pub const WORMHOLE_CONSISTENCY_LEVEL: u8 = 1; // Same as Ethereum contract
+
+// Metaplex royalty paid to the collection authority
+pub const SELLER_FEE_BASIS_POINTS: u16 = 500;
\ No newline at end of file
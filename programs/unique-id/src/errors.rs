@@ -20,4 +20,7 @@ pub enum ErrorCode {
     
     #[msg("Token ID not found")]
     TokenIdNotFound,
+
+    #[msg("Metadata name or URI exceeds the Metaplex limits")]
+    MetadataTooLong,
 }
\ No newline at end of file
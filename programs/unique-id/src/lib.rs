@@ -13,13 +13,8 @@ use crate::events::*;
 use crate::state::*;
 
 use anchor_spl::{
-    associated_token::AssociatedToken,
-    metadata::{
-        create_metadata_accounts_v3,
-        mpl_token_metadata::types::{Creator, DataV2},
-        CreateMetadataAccountsV3, Metadata,
-    },
-    token_interface::{mint_to, Mint, MintTo, TokenInterface},
+    metadata::{create_metadata_accounts_v3, CreateMetadataAccountsV3},
+    token_interface::{mint_to, MintTo},
 };
 use solana_program::{
     hash::hash,
@@ -104,6 +99,82 @@ pub mod unique_id {
 
         Ok(())
     }
+
+    /// `mint_nft` plus a Metaplex metadata account for the new token, with
+    /// the collection PDA as its update authority.
+    pub fn mint_nft_with_metadata(ctx: Context<MintNftWithMetadata>) -> Result<()> {
+        let collection = &mut ctx.accounts.collection;
+        let user_state = &mut ctx.accounts.user_state;
+
+        // Generate unique ID
+        let unique_id =
+            generate_unique_id(SOLANA_CHAIN_ID, &ctx.accounts.user.key(), user_state.nonce)?;
+
+        // Check if unique ID already exists
+        require!(
+            !collection.unique_id_exists(unique_id),
+            ErrorCode::UniqueIdAlreadyExists
+        );
+
+        // Increment counters
+        collection.total_supply += 1;
+        user_state.nonce += 1;
+
+        let token_id = collection.total_supply;
+        let data = collection.metadata_for(token_id)?;
+
+        // Store mappings
+        collection.add_unique_id_mapping(unique_id, token_id);
+        collection.mint_to_unique_id.push(MintToUniqueId {
+            mint: ctx.accounts.mint.key(),
+            unique_id,
+        });
+
+        let collection_seeds = &[COLLECTION_SEED, &[collection.bump]];
+        let signer = &[&collection_seeds[..]];
+
+        mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.token_account.to_account_info(),
+                    authority: collection.to_account_info(),
+                },
+                signer,
+            ),
+            1,
+        )?;
+
+        create_metadata_accounts_v3(
+            CpiContext::new_with_signer(
+                ctx.accounts.metadata_program.to_account_info(),
+                CreateMetadataAccountsV3 {
+                    metadata: ctx.accounts.metadata.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    mint_authority: collection.to_account_info(),
+                    payer: ctx.accounts.user.to_account_info(),
+                    update_authority: collection.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+                signer,
+            ),
+            data,
+            true,
+            true,
+            None,
+        )?;
+
+        emit!(NftMinted {
+            user: ctx.accounts.user.key(),
+            token_id,
+            unique_id,
+            nonce: user_state.nonce - 1,
+        });
+
+        Ok(())
+    }
     pub fn request_cross_chain_mint(
         ctx: Context<RequestCrossChainMint>,
         nonce: u32,
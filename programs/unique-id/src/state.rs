@@ -1,6 +1,10 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
+    metadata::mpl_token_metadata::{
+        types::{Creator, DataV2},
+        MAX_NAME_LENGTH, MAX_URI_LENGTH,
+    },
     token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
@@ -55,6 +59,31 @@ impl Collection {
             .map(|mapping| mapping.unique_id)
             .ok_or(error!(ErrorCode::TokenIdNotFound))
     }
+
+    /// Metaplex metadata for `token_id`: "{name} #{token_id}" pointing at
+    /// "{base_uri}{token_id}.json", with the authority as sole creator.
+    pub fn metadata_for(&self, token_id: u64) -> Result<DataV2> {
+        let name = format!("{} #{}", self.name, token_id);
+        let uri = format!("{}{}.json", self.base_uri, token_id);
+        require!(
+            name.len() <= MAX_NAME_LENGTH && uri.len() <= MAX_URI_LENGTH,
+            ErrorCode::MetadataTooLong
+        );
+
+        Ok(DataV2 {
+            name,
+            symbol: self.symbol.clone(),
+            uri,
+            seller_fee_basis_points: SELLER_FEE_BASIS_POINTS,
+            creators: Some(vec![Creator {
+                address: self.authority,
+                verified: false,
+                share: 100,
+            }]),
+            collection: None,
+            uses: None,
+        })
+    }
 }
 
 #[derive(Accounts)]
@@ -117,7 +146,6 @@ pub struct MintNft<'info> {
     pub rent: Sysvar<'info, Rent>,
 }
 
-// Optional: Keep a separate struct for minting with metadata later
 #[derive(Accounts)]
 pub struct MintNftWithMetadata<'info> {
     #[account(mut, seeds = [COLLECTION_SEED], bump = collection.bump)]
@@ -151,8 +179,13 @@ pub struct MintNftWithMetadata<'info> {
     )]
     pub token_account: InterfaceAccount<'info, TokenAccount>,
 
-    /// CHECK: Metadata account for Metaplex
-    #[account(mut)]
+    /// CHECK: Metaplex metadata PDA for `mint`, created by the metadata program
+    #[account(
+        mut,
+        seeds = [b"metadata", metadata_program.key().as_ref(), mint.key().as_ref()],
+        bump,
+        seeds::program = metadata_program.key()
+    )]
     pub metadata: UncheckedAccount<'info>,
 
     #[account(mut)]
@@ -1,18 +1,30 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
 use anchor_spl::{
     associated_token::AssociatedToken,
     metadata::{
-        create_metadata_accounts_v3,
-        mpl_token_metadata::types::{Creator, DataV2},
-        CreateMetadataAccountsV3, Metadata,
+        create_master_edition_v3, create_metadata_accounts_v3,
+        mpl_token_metadata::{
+            types::{CollectionDetails, Creator, DataV2},
+            MAX_NAME_LENGTH, MAX_URI_LENGTH,
+        },
+        set_and_verify_sized_collection_item, CreateMasterEditionV3, CreateMetadataAccountsV3,
+        Metadata, SetAndVerifySizedCollectionItem,
+    },
+    token::{
+        mint_to, transfer as token_transfer, Mint, MintTo, Token, TokenAccount,
+        Transfer as TokenTransfer,
     },
-    token::{mint_to, Mint, MintTo, Token, TokenAccount},
 };
 use solana_program::{
     hash::hash,
     instruction::{AccountMeta, Instruction},
     program::invoke_signed,
 };
+use wormhole_supported_chains::Chain;
+
+pub mod payload;
+use payload::{Message, MintRequest, PositionSnapshot, PositionTransfer};
 
 declare_id!("ACM92DSXA9ebVyxQpZf4EKDvuEgXsiAkD6Us8kTcGVh7");
 
@@ -23,15 +35,40 @@ declare_id!("ACM92DSXA9ebVyxQpZf4EKDvuEgXsiAkD6Us8kTcGVh7");
 // Seeds for PDA derivation
 pub const COLLECTION_SEED: &[u8] = b"collection_v2";
 pub const USER_STATE_SEED: &[u8] = b"user_state_v2";
+pub const UNIQUE_ID_SEED: &[u8] = b"unique_id";
+pub const TOKEN_ID_SEED: &[u8] = b"token_id";
+pub const MINT_RECORD_SEED: &[u8] = b"mint_record";
+pub const COLLECTION_MINT_SEED: &[u8] = b"collection_mint";
 
-// Maximum sizes for collection vectors
-pub const MAX_COLLECTION_SIZE: usize = 50;
-
-// Chain IDs
-pub const SOLANA_CHAIN_ID: u64 = 1; // Solana mainnet chain ID for consistency with Ethereum
+// Chain ID mixed into unique IDs, a u64 like the Ethereum contract's
+// `block.chainid`. Wormhole chain IDs are `wormhole_supported_chains::Chain`.
+pub const SOLANA_CHAIN_ID: u64 = 1;
 
 // Wormhole constants
 pub const WORMHOLE_CONSISTENCY_LEVEL: u8 = 1; // Same as Ethereum contract
+pub const WORMHOLE_MAINNET_PROGRAM_ID: Pubkey =
+    pubkey!("worm2ZoG2kUd4vFXhvjh93UUH596ayRfgQ2MgjNMTth");
+pub const EMITTER_SEED: &[u8] = b"emitter";
+pub const MESSAGE_SEED: &[u8] = b"message";
+// Core bridge PDAs, derived under the collection's `wormhole_program_id`
+pub const WORMHOLE_BRIDGE_SEED: &[u8] = b"Bridge";
+pub const WORMHOLE_FEE_COLLECTOR_SEED: &[u8] = b"fee_collector";
+pub const WORMHOLE_SEQUENCE_SEED: &[u8] = b"Sequence";
+pub const WORMHOLE_POSTED_VAA_SEED: &[u8] = b"PostedVAA";
+// Core bridge instruction index of post_message
+pub const WORMHOLE_POST_MESSAGE_IX: u8 = 1;
+// Data prefix of a guardian-verified VAA posted by the core bridge
+pub const WORMHOLE_POSTED_VAA_PREFIX: &[u8] = b"vaa";
+pub const FOREIGN_EMITTER_SEED: &[u8] = b"foreign_emitter";
+pub const CLAIMED_VAA_SEED: &[u8] = b"claimed_vaa";
+// simple_vault, whose `[POSITION_AUTHORITY_SEED]` PDA vouches for the
+// position snapshot in a `PositionTransfer` message
+pub const POSITION_VAULT_PROGRAM_ID: Pubkey =
+    pubkey!("DGXrmuhPvYJEWytSpZPB3PCA2zNvSsNvctkAeS924473");
+pub const POSITION_AUTHORITY_SEED: &[u8] = b"position_authority";
+
+// Metaplex royalty paid to the collection authority
+pub const SELLER_FEE_BASIS_POINTS: u16 = 500;
 
 // ================================
 // ERRORS
@@ -41,21 +78,63 @@ pub const WORMHOLE_CONSISTENCY_LEVEL: u8 = 1; // Same as Ethereum contract
 pub enum ErrorCode {
     #[msg("Unique ID already exists")]
     UniqueIdAlreadyExists,
-    
+
     #[msg("Not the token owner")]
     NotTokenOwner,
-    
+
     #[msg("Invalid sequence data")]
     InvalidSequenceData,
-    
+
     #[msg("Mint not found")]
     MintNotFound,
-    
+
     #[msg("Unique ID not found")]
     UniqueIdNotFound,
-    
+
     #[msg("Token ID not found")]
     TokenIdNotFound,
+
+    #[msg("Unauthorized")]
+    Unauthorized,
+
+    #[msg("Legacy registry entries still need to be migrated")]
+    RegistryMigrationPending,
+
+    #[msg("Metadata name or URI exceeds the Metaplex limits")]
+    MetadataTooLong,
+
+    #[msg("Not the collection's Wormhole core bridge")]
+    InvalidWormholeProgram,
+
+    #[msg("Malformed Wormhole bridge account")]
+    InvalidBridgeData,
+
+    #[msg("Not a VAA posted by the collection's Wormhole core bridge")]
+    InvalidPostedVaa,
+
+    #[msg("VAA was not sent by the registered emitter for its chain")]
+    UnknownEmitter,
+
+    #[msg("Malformed cross-chain payload")]
+    InvalidPayload,
+
+    #[msg("Cross-chain mint is not addressed to Solana")]
+    WrongTargetChain,
+
+    #[msg("Recipient does not match the payload")]
+    RecipientMismatch,
+
+    #[msg("Not a foreign Wormhole chain")]
+    UnsupportedChain,
+
+    #[msg("Emitter address cannot be zero")]
+    InvalidEmitterAddress,
+
+    #[msg("NFT is not escrowed for a cross-chain transfer")]
+    NotBridgedOut,
+
+    #[msg("Not a collection account")]
+    InvalidCollectionAccount,
 }
 
 // ================================
@@ -79,56 +158,223 @@ pub struct CrossChainMintRequested {
     pub sequence: u64,
 }
 
+#[event]
+pub struct CrossChainMintReceived {
+    pub recipient: Pubkey,
+    pub mint: Pubkey,
+    pub unique_id: [u8; 32],
+    pub token_id: u64,
+    pub emitter_chain: u16,
+    pub sequence: u64,
+}
+
+#[event]
+pub struct CrossChainNftReleased {
+    pub recipient: Pubkey,
+    pub mint: Pubkey,
+    pub unique_id: [u8; 32],
+    pub emitter_chain: u16,
+    pub sequence: u64,
+}
+
+#[event]
+pub struct ForeignEmitterRegistered {
+    pub chain: u16,
+    pub address: [u8; 32],
+}
+
+#[event]
+pub struct ForeignEmitterDeregistered {
+    pub chain: u16,
+    pub address: [u8; 32],
+}
+
+#[event]
+pub struct RegistryEntryMigrated {
+    pub unique_id: [u8; 32],
+    pub token_id: u64,
+    pub mint: Pubkey,
+    pub cross_chain: bool,
+}
+
 // ================================
 // STATE STRUCTS
 // ================================
 
-#[derive(InitSpace, AnchorSerialize, AnchorDeserialize, Clone)]
+#[account]
+#[derive(InitSpace)]
+pub struct Collection {
+    pub authority: Pubkey,
+    #[max_len(32)]
+    pub name: String,
+    #[max_len(8)]
+    pub symbol: String,
+    #[max_len(200)]
+    pub base_uri: String,
+    pub total_supply: u64,
+    pub wormhole_program_id: Pubkey,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct UserState {
+    pub nonce: u64,
+}
+
+// One record per minted NFT under each lookup key, so lookups are a PDA
+// derivation instead of a scan and supply is not bounded by account size.
+
+/// `[UNIQUE_ID_SEED, unique_id]`
+#[account]
+#[derive(InitSpace)]
+pub struct UniqueIdRecord {
+    pub unique_id: [u8; 32],
+    pub token_id: u64,
+    pub mint: Pubkey,
+    /// Set while the NFT is escrowed in the collection's token account
+    /// because the identity lives on another chain
+    pub cross_chain: bool,
+    pub bump: u8,
+}
+
+/// `[TOKEN_ID_SEED, token_id.to_le_bytes()]`
+#[account]
+#[derive(InitSpace)]
+pub struct TokenIdRecord {
+    pub token_id: u64,
+    pub unique_id: [u8; 32],
+    pub bump: u8,
+}
+
+/// `[MINT_RECORD_SEED, mint]`
+#[account]
+#[derive(InitSpace)]
+pub struct MintRecord {
+    pub mint: Pubkey,
+    pub unique_id: [u8; 32],
+    pub token_id: u64,
+    pub bump: u8,
+}
+
+/// `[FOREIGN_EMITTER_SEED, chain.to_le_bytes()]`, the one contract on
+/// `chain` whose messages `receive_cross_chain_mint` accepts
+#[account]
+#[derive(InitSpace)]
+pub struct ForeignEmitter {
+    pub chain: u16,
+    pub address: [u8; 32],
+    pub bump: u8,
+}
+
+/// `[CLAIMED_VAA_SEED, vaa_hash]`, created when a VAA is redeemed so it
+/// cannot be redeemed twice
+#[account]
+#[derive(InitSpace)]
+pub struct ClaimedVaa {
+    pub emitter_chain: u16,
+    pub sequence: u64,
+    pub bump: u8,
+}
+
+// ================================
+// LEGACY REGISTRY
+// ================================
+
+// Collections created before the per-record PDAs kept every mapping in
+// bounded Vecs after the fields above. `Collection` still deserializes from
+// those accounts, the Vecs are read from the tail by `migrate_registry_entry`.
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct UniqueIdToTokenId {
     pub unique_id: [u8; 32],
     pub token_id: u64,
 }
 
-#[derive(InitSpace, AnchorSerialize, AnchorDeserialize, Clone)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct TokenIdToUniqueId {
     pub token_id: u64,
     pub unique_id: [u8; 32],
 }
 
-#[derive(InitSpace, AnchorSerialize, AnchorDeserialize, Clone)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct MintToUniqueId {
     pub mint: Pubkey,
     pub unique_id: [u8; 32],
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct Collection {
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyCollection {
     pub authority: Pubkey,
-    #[max_len(32)]
     pub name: String,
-    #[max_len(8)]
     pub symbol: String,
-    #[max_len(200)]
     pub base_uri: String,
     pub total_supply: u64,
     pub wormhole_program_id: Pubkey,
     pub bump: u8,
-
-    #[max_len(MAX_COLLECTION_SIZE)]
     pub unique_id_to_token_id: Vec<UniqueIdToTokenId>,
-    #[max_len(MAX_COLLECTION_SIZE)]
     pub token_id_to_unique_id: Vec<TokenIdToUniqueId>,
-    #[max_len(MAX_COLLECTION_SIZE)]
     pub mint_to_unique_id: Vec<MintToUniqueId>,
-    #[max_len(MAX_COLLECTION_SIZE)]
     pub cross_chain_unique_ids: Vec<[u8; 32]>,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct UserState {
-    pub nonce: u64,
+impl LegacyCollection {
+    /// Reads the legacy layout from a collection account. Accounts created
+    /// without the Vecs, or already trimmed by the migration, yield `None`.
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        let data = info.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == *Collection::DISCRIMINATOR,
+            ErrorCode::InvalidCollectionAccount
+        );
+        Ok(Self::deserialize(&mut &data[8..]).ok())
+    }
+
+    pub fn store(&self, info: &AccountInfo) -> Result<()> {
+        let mut data = info.try_borrow_mut_data()?;
+        self.serialize(&mut &mut data[8..])?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.unique_id_to_token_id.is_empty()
+            && self.token_id_to_unique_id.is_empty()
+            && self.mint_to_unique_id.is_empty()
+            && self.cross_chain_unique_ids.is_empty()
+    }
+
+    /// Removes every legacy mapping of `unique_id`, checking that they agree
+    /// with `token_id` and `mint`. Returns whether it was marked cross-chain.
+    pub fn take_entry(
+        &mut self,
+        unique_id: [u8; 32],
+        token_id: u64,
+        mint: &Pubkey,
+    ) -> Result<bool> {
+        let by_unique_id = self
+            .unique_id_to_token_id
+            .iter()
+            .position(|m| m.unique_id == unique_id && m.token_id == token_id)
+            .ok_or(error!(ErrorCode::UniqueIdNotFound))?;
+        let by_token_id = self
+            .token_id_to_unique_id
+            .iter()
+            .position(|m| m.token_id == token_id && m.unique_id == unique_id)
+            .ok_or(error!(ErrorCode::TokenIdNotFound))?;
+        let by_mint = self
+            .mint_to_unique_id
+            .iter()
+            .position(|m| m.mint == *mint && m.unique_id == unique_id)
+            .ok_or(error!(ErrorCode::MintNotFound))?;
+
+        self.unique_id_to_token_id.swap_remove(by_unique_id);
+        self.token_id_to_unique_id.swap_remove(by_token_id);
+        self.mint_to_unique_id.swap_remove(by_mint);
+
+        let cross_chain = self.cross_chain_unique_ids.contains(&unique_id);
+        self.cross_chain_unique_ids.retain(|id| *id != unique_id);
+        Ok(cross_chain)
+    }
 }
 
 // ================================
@@ -136,53 +382,170 @@ pub struct UserState {
 // ================================
 
 impl Collection {
-    pub fn unique_id_exists(&self, unique_id: [u8; 32]) -> bool {
-        self.unique_id_to_token_id
-            .iter()
-            .any(|mapping| mapping.unique_id == unique_id)
+    pub fn next_token_id(&self) -> u64 {
+        self.total_supply + 1
     }
 
-    pub fn add_unique_id_mapping(&mut self, unique_id: [u8; 32], token_id: u64) {
-        self.unique_id_to_token_id.push(UniqueIdToTokenId {
-            unique_id,
-            token_id,
-        });
-        
-        self.token_id_to_unique_id.push(TokenIdToUniqueId {
-            token_id,
-            unique_id,
-        });
+    /// Metaplex metadata for `token_id`: "{name} #{token_id}" pointing at
+    /// "{base_uri}{token_id}.json", with the authority as sole creator.
+    pub fn metadata_for(&self, token_id: u64) -> Result<DataV2> {
+        self.metadata(
+            format!("{} #{}", self.name, token_id),
+            format!("{}{}.json", self.base_uri, token_id),
+        )
     }
 
-    pub fn get_unique_id_by_mint(&self, mint: &Pubkey) -> Result<[u8; 32]> {
-        self.mint_to_unique_id
-            .iter()
-            .find(|mapping| mapping.mint == *mint)
-            .map(|mapping| mapping.unique_id)
-            .ok_or(error!(ErrorCode::MintNotFound))
+    /// Metaplex metadata for the collection NFT itself.
+    pub fn collection_metadata(&self) -> Result<DataV2> {
+        self.metadata(
+            self.name.clone(),
+            format!("{}collection.json", self.base_uri),
+        )
     }
 
-    pub fn mark_cross_chain(&mut self, unique_id: [u8; 32]) {
-        if !self.cross_chain_unique_ids.contains(&unique_id) {
-            self.cross_chain_unique_ids.push(unique_id);
-        }
+    fn metadata(&self, name: String, uri: String) -> Result<DataV2> {
+        require!(
+            name.len() <= MAX_NAME_LENGTH && uri.len() <= MAX_URI_LENGTH,
+            ErrorCode::MetadataTooLong
+        );
+
+        Ok(DataV2 {
+            name,
+            symbol: self.symbol.clone(),
+            uri,
+            seller_fee_basis_points: SELLER_FEE_BASIS_POINTS,
+            creators: Some(vec![Creator {
+                address: self.authority,
+                verified: false,
+                share: 100,
+            }]),
+            collection: None,
+            uses: None,
+        })
     }
+}
 
-    pub fn get_token_id_by_unique_id(&self, unique_id: [u8; 32]) -> Result<u64> {
-        self.unique_id_to_token_id
-            .iter()
-            .find(|mapping| mapping.unique_id == unique_id)
-            .map(|mapping| mapping.token_id)
-            .ok_or(error!(ErrorCode::UniqueIdNotFound))
+impl UserState {
+    pub fn next_unique_id(&self, user: &Pubkey) -> [u8; 32] {
+        generate_unique_id(SOLANA_CHAIN_ID, user, self.nonce)
     }
+}
 
-    pub fn get_unique_id_by_token_id(&self, token_id: u64) -> Result<[u8; 32]> {
-        self.token_id_to_unique_id
-            .iter()
-            .find(|mapping| mapping.token_id == token_id)
-            .map(|mapping| mapping.unique_id)
-            .ok_or(error!(ErrorCode::TokenIdNotFound))
+/// The collection NFT every `mint_nft_with_metadata` item is verified into.
+pub fn collection_mint_address() -> Pubkey {
+    Pubkey::find_program_address(&[COLLECTION_MINT_SEED], &ID).0
+}
+
+/// Everything needed to mint one verified collection item. The collection
+/// PDA is mint authority of `mint` and update authority of the collection NFT.
+pub struct CollectionItem<'info> {
+    pub collection: AccountInfo<'info>,
+    pub collection_bump: u8,
+    pub mint: AccountInfo<'info>,
+    pub token_account: AccountInfo<'info>,
+    pub metadata: AccountInfo<'info>,
+    pub master_edition: AccountInfo<'info>,
+    pub collection_mint: AccountInfo<'info>,
+    pub collection_metadata: AccountInfo<'info>,
+    pub collection_master_edition: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub metadata_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: AccountInfo<'info>,
+}
+
+impl CollectionItem<'_> {
+    /// Mints the single token, creates the metadata and a master edition
+    /// (which locks supply at 1 and takes the mint authority), then sets and
+    /// verifies the item into the collection NFT.
+    pub fn mint(self, data: DataV2) -> Result<()> {
+        let collection_seeds = &[COLLECTION_SEED, &[self.collection_bump]];
+        let signer = &[&collection_seeds[..]];
+
+        mint_to(
+            CpiContext::new_with_signer(
+                self.token_program.clone(),
+                MintTo {
+                    mint: self.mint.clone(),
+                    to: self.token_account.clone(),
+                    authority: self.collection.clone(),
+                },
+                signer,
+            ),
+            1,
+        )?;
+
+        create_metadata_accounts_v3(
+            CpiContext::new_with_signer(
+                self.metadata_program.clone(),
+                CreateMetadataAccountsV3 {
+                    metadata: self.metadata.clone(),
+                    mint: self.mint.clone(),
+                    mint_authority: self.collection.clone(),
+                    payer: self.payer.clone(),
+                    update_authority: self.collection.clone(),
+                    system_program: self.system_program.clone(),
+                    rent: self.rent.clone(),
+                },
+                signer,
+            ),
+            data,
+            true,
+            true,
+            None,
+        )?;
+
+        create_master_edition_v3(
+            CpiContext::new_with_signer(
+                self.metadata_program.clone(),
+                CreateMasterEditionV3 {
+                    edition: self.master_edition,
+                    mint: self.mint,
+                    update_authority: self.collection.clone(),
+                    mint_authority: self.collection.clone(),
+                    payer: self.payer.clone(),
+                    metadata: self.metadata.clone(),
+                    token_program: self.token_program,
+                    system_program: self.system_program,
+                    rent: self.rent,
+                },
+                signer,
+            ),
+            Some(0),
+        )?;
+
+        set_and_verify_sized_collection_item(
+            CpiContext::new_with_signer(
+                self.metadata_program,
+                SetAndVerifySizedCollectionItem {
+                    metadata: self.metadata,
+                    collection_authority: self.collection.clone(),
+                    payer: self.payer,
+                    update_authority: self.collection,
+                    collection_mint: self.collection_mint,
+                    collection_metadata: self.collection_metadata,
+                    collection_master_edition: self.collection_master_edition,
+                },
+                signer,
+            ),
+            None,
+        )
+    }
+}
+
+/// A registry record exists once this program has initialised it. Records
+/// are never closed, so existence is all that `unique_id_exists` and
+/// collection membership checks need.
+pub fn record_exists(record: &AccountInfo) -> bool {
+    record.owner == &ID && !record.data_is_empty()
+}
+
+fn load_record<T: AccountDeserialize>(record: &AccountInfo, missing: ErrorCode) -> Result<T> {
+    if !record_exists(record) {
+        return Err(missing.into());
     }
+    T::try_deserialize(&mut &record.try_borrow_data()?[..])
 }
 
 // ================================
@@ -200,10 +563,56 @@ pub struct Initialize<'info> {
     )]
     pub collection: Account<'info, Collection>,
 
+    #[account(
+        init,
+        payer = authority,
+        seeds = [COLLECTION_MINT_SEED],
+        bump,
+        mint::decimals = 0,
+        mint::authority = collection,
+        mint::freeze_authority = collection,
+    )]
+    pub collection_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = collection_mint,
+        associated_token::authority = collection,
+    )]
+    pub collection_token: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Metaplex metadata PDA for `collection_mint`, created by the metadata program
+    #[account(
+        mut,
+        seeds = [b"metadata", metadata_program.key().as_ref(), collection_mint.key().as_ref()],
+        bump,
+        seeds::program = metadata_program.key()
+    )]
+    pub collection_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Metaplex master edition PDA for `collection_mint`, created by the metadata program
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            collection_mint.key().as_ref(),
+            b"edition"
+        ],
+        bump,
+        seeds::program = metadata_program.key()
+    )]
+    pub collection_master_edition: UncheckedAccount<'info>,
+
     #[account(mut)]
     pub authority: Signer<'info>,
 
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub metadata_program: Program<'info, Metadata>,
     pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
@@ -229,6 +638,33 @@ pub struct MintNft<'info> {
     )]
     pub mint: Account<'info, Mint>,
 
+    #[account(
+        init,
+        payer = user,
+        space = 8 + UniqueIdRecord::INIT_SPACE,
+        seeds = [UNIQUE_ID_SEED, user_state.next_unique_id(&user.key()).as_ref()],
+        bump
+    )]
+    pub unique_id_record: Account<'info, UniqueIdRecord>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + TokenIdRecord::INIT_SPACE,
+        seeds = [TOKEN_ID_SEED, collection.next_token_id().to_le_bytes().as_ref()],
+        bump
+    )]
+    pub token_id_record: Account<'info, TokenIdRecord>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + MintRecord::INIT_SPACE,
+        seeds = [MINT_RECORD_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub mint_record: Account<'info, MintRecord>,
+
     #[account(
         init,
         payer = user,
@@ -269,6 +705,33 @@ pub struct MintNftWithMetadata<'info> {
     )]
     pub mint: Account<'info, Mint>,
 
+    #[account(
+        init,
+        payer = user,
+        space = 8 + UniqueIdRecord::INIT_SPACE,
+        seeds = [UNIQUE_ID_SEED, user_state.next_unique_id(&user.key()).as_ref()],
+        bump
+    )]
+    pub unique_id_record: Account<'info, UniqueIdRecord>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + TokenIdRecord::INIT_SPACE,
+        seeds = [TOKEN_ID_SEED, collection.next_token_id().to_le_bytes().as_ref()],
+        bump
+    )]
+    pub token_id_record: Account<'info, TokenIdRecord>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + MintRecord::INIT_SPACE,
+        seeds = [MINT_RECORD_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub mint_record: Account<'info, MintRecord>,
+
     #[account(
         init,
         payer = user,
@@ -277,10 +740,55 @@ pub struct MintNftWithMetadata<'info> {
     )]
     pub token_account: Account<'info, TokenAccount>,
 
-    /// CHECK: Metadata account for Metaplex
-    #[account(mut)]
+    /// CHECK: Metaplex metadata PDA for `mint`, created by the metadata program
+    #[account(
+        mut,
+        seeds = [b"metadata", metadata_program.key().as_ref(), mint.key().as_ref()],
+        bump,
+        seeds::program = metadata_program.key()
+    )]
     pub metadata: UncheckedAccount<'info>,
 
+    /// CHECK: Metaplex master edition PDA for `mint`, created by the metadata program
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            mint.key().as_ref(),
+            b"edition"
+        ],
+        bump,
+        seeds::program = metadata_program.key()
+    )]
+    pub master_edition: UncheckedAccount<'info>,
+
+    /// CHECK: The collection NFT's mint, created at `initialize`
+    #[account(seeds = [COLLECTION_MINT_SEED], bump)]
+    pub collection_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Metaplex metadata PDA for `collection_mint`, its size is updated
+    #[account(
+        mut,
+        seeds = [b"metadata", metadata_program.key().as_ref(), collection_mint.key().as_ref()],
+        bump,
+        seeds::program = metadata_program.key()
+    )]
+    pub collection_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Metaplex master edition PDA for `collection_mint`
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            collection_mint.key().as_ref(),
+            b"edition"
+        ],
+        bump,
+        seeds::program = metadata_program.key()
+    )]
+    pub collection_master_edition: UncheckedAccount<'info>,
+
     #[account(mut)]
     pub user: Signer<'info>,
 
@@ -292,60 +800,595 @@ pub struct MintNftWithMetadata<'info> {
 }
 
 #[derive(Accounts)]
+#[instruction(nonce: u32, target_chain_id: u16)]
 pub struct RequestCrossChainMint<'info> {
-    #[account(mut, seeds = [COLLECTION_SEED], bump = collection.bump)]
+    #[account(seeds = [COLLECTION_SEED], bump = collection.bump)]
     pub collection: Account<'info, Collection>,
 
+    /// The contract that will redeem the message on `target_chain_id`
+    #[account(
+        seeds = [FOREIGN_EMITTER_SEED, target_chain_id.to_le_bytes().as_ref()],
+        bump = foreign_emitter.bump
+    )]
+    pub foreign_emitter: Account<'info, ForeignEmitter>,
+
     #[account(mut)]
     pub mint: Account<'info, Mint>,
 
     #[account(
+        seeds = [MINT_RECORD_SEED, mint.key().as_ref()],
+        bump = mint_record.bump
+    )]
+    pub mint_record: Account<'info, MintRecord>,
+
+    #[account(
+        mut,
+        seeds = [UNIQUE_ID_SEED, mint_record.unique_id.as_ref()],
+        bump = unique_id_record.bump
+    )]
+    pub unique_id_record: Account<'info, UniqueIdRecord>,
+
+    #[account(
+        mut,
         constraint = token_account.mint == mint.key(),
         constraint = token_account.owner == user.key(),
     )]
     pub token_account: Account<'info, TokenAccount>,
 
+    /// Holds the NFT while its identity lives on another chain
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint,
+        associated_token::authority = collection,
+    )]
+    pub escrow_token_account: Box<Account<'info, TokenAccount>>,
+
     #[account(mut)]
-    pub user: Signer<'info>,
-
-    /// CHECK: Wormhole bridge account
-    pub wormhole_bridge: UncheckedAccount<'info>,
-    /// CHECK: Wormhole message account  
-    #[account(mut)]
-    pub wormhole_message: UncheckedAccount<'info>,
-    /// CHECK: Wormhole emitter account
-    pub wormhole_emitter: UncheckedAccount<'info>,
-    /// CHECK: Wormhole sequence account
-    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: This program's emitter, signs every message it posts
+    #[account(seeds = [EMITTER_SEED], bump)]
+    pub wormhole_emitter: UncheckedAccount<'info>,
+
+    /// CHECK: The emitter's sequence tracker, created by the core bridge on
+    /// the first message
+    #[account(
+        mut,
+        seeds = [WORMHOLE_SEQUENCE_SEED, wormhole_emitter.key().as_ref()],
+        bump,
+        seeds::program = wormhole_program.key()
+    )]
     pub wormhole_sequence: UncheckedAccount<'info>,
-    /// CHECK: Wormhole fee collector
-    #[account(mut)]
+
+    /// CHECK: Posted message account for the next sequence, created by the
+    /// core bridge
+    #[account(
+        mut,
+        seeds = [MESSAGE_SEED, &wormhole_sequence_value(&wormhole_sequence)?.to_le_bytes()],
+        bump
+    )]
+    pub wormhole_message: UncheckedAccount<'info>,
+
+    /// CHECK: Core bridge config, holds the message fee
+    #[account(
+        mut,
+        seeds = [WORMHOLE_BRIDGE_SEED],
+        bump,
+        seeds::program = wormhole_program.key()
+    )]
+    pub wormhole_bridge: UncheckedAccount<'info>,
+
+    /// CHECK: Core bridge fee collector
+    #[account(
+        mut,
+        seeds = [WORMHOLE_FEE_COLLECTOR_SEED],
+        bump,
+        seeds::program = wormhole_program.key()
+    )]
     pub wormhole_fee_collector: UncheckedAccount<'info>,
 
-    pub wormhole_program: Program<'info, WormholeProgram>,
+    /// CHECK: The core bridge configured at `initialize`
+    #[account(
+        executable,
+        address = collection.wormhole_program_id @ ErrorCode::InvalidWormholeProgram
+    )]
+    pub wormhole_program: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
     pub clock: Sysvar<'info, Clock>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+impl RequestCrossChainMint<'_> {
+    /// Escrows the NFT and posts its cross-chain message.
+    fn send(
+        &mut self,
+        bumps: &RequestCrossChainMintBumps,
+        nonce: u32,
+        target_chain_id: u16,
+        recipient: [u8; 32],
+        position: Option<PositionSnapshot>,
+    ) -> Result<()> {
+        foreign_chain(target_chain_id)?;
+
+        // Verify token ownership by checking token account
+        require!(self.token_account.amount == 1, ErrorCode::NotTokenOwner);
+        require!(
+            self.token_account.owner == self.user.key(),
+            ErrorCode::NotTokenOwner
+        );
+
+        // The mint record PDA maps the mint to its unique_id
+        let unique_id = self.mint_record.unique_id;
+
+        // Lock the NFT before announcing it elsewhere, so the identity is
+        // only usable on one chain at a time
+        token_transfer(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TokenTransfer {
+                    from: self.token_account.to_account_info(),
+                    to: self.escrow_token_account.to_account_info(),
+                    authority: self.user.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+
+        let mint = MintRequest {
+            recipient,
+            unique_id,
+            target_chain_id,
+        };
+        let payload = match position {
+            Some(position) => Message::PositionTransfer(PositionTransfer { mint, position }),
+            None => Message::MintRequest(mint),
+        }
+        .encode()?;
+
+        // The core bridge takes its fee from the collector's balance, so
+        // it is paid in before posting
+        let fee = wormhole_bridge_fee(&self.wormhole_bridge)?;
+        if fee > 0 {
+            transfer(
+                CpiContext::new(
+                    self.system_program.to_account_info(),
+                    Transfer {
+                        from: self.user.to_account_info(),
+                        to: self.wormhole_fee_collector.to_account_info(),
+                    },
+                ),
+                fee,
+            )?;
+        }
+
+        let wormhole_accounts = PublishMessage {
+            bridge: self.wormhole_bridge.to_account_info(),
+            message: self.wormhole_message.to_account_info(),
+            emitter: self.wormhole_emitter.to_account_info(),
+            sequence: self.wormhole_sequence.to_account_info(),
+            payer: self.user.to_account_info(),
+            fee_collector: self.wormhole_fee_collector.to_account_info(),
+            clock: self.clock.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            rent: self.rent.to_account_info(),
+        };
+
+        // The emitter PDA signs as the message's emitter and the message PDA
+        // signs for its own creation
+        let message_sequence = wormhole_sequence_value(&self.wormhole_sequence)?;
+        let message_sequence_bytes = message_sequence.to_le_bytes();
+        let emitter_seeds: &[&[u8]] = &[EMITTER_SEED, &[bumps.wormhole_emitter]];
+        let message_seeds: &[&[u8]] = &[
+            MESSAGE_SEED,
+            &message_sequence_bytes,
+            &[bumps.wormhole_message],
+        ];
+        let signer = &[emitter_seeds, message_seeds];
+
+        let wormhole_ctx = CpiContext::new_with_signer(
+            self.wormhole_program.to_account_info(),
+            wormhole_accounts,
+            signer,
+        );
+        publish_message(wormhole_ctx, nonce, payload, WORMHOLE_CONSISTENCY_LEVEL)?;
+
+        // Mark as escrowed in the registry
+        self.unique_id_record.cross_chain = true;
+
+        // The tracker now holds the next sequence, this message took the one
+        // before it
+        let sequence = wormhole_sequence_value(&self.wormhole_sequence)?
+            .checked_sub(1)
+            .ok_or(ErrorCode::InvalidSequenceData)?;
+        require!(sequence == message_sequence, ErrorCode::InvalidSequenceData);
+
+        emit!(CrossChainMintRequested {
+            sender: self.user.key(),
+            mint: self.mint.key(),
+            unique_id,
+            target_chain_id,
+            sequence,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u32, target_chain_id: u16)]
+pub struct RequestCrossChainTransfer<'info> {
+    pub request: RequestCrossChainMint<'info>,
+
+    /// simple_vault's signer PDA, only obtainable through its `bridge_position`
+    #[account(seeds = [POSITION_AUTHORITY_SEED], bump, seeds::program = POSITION_VAULT_PROGRAM_ID)]
+    pub position_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain: u16)]
+pub struct RegisterEmitter<'info> {
+    #[account(
+        seeds = [COLLECTION_SEED],
+        bump = collection.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub collection: Account<'info, Collection>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ForeignEmitter::INIT_SPACE,
+        seeds = [FOREIGN_EMITTER_SEED, chain.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub foreign_emitter: Account<'info, ForeignEmitter>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain: u16)]
+pub struct DeregisterEmitter<'info> {
+    #[account(
+        seeds = [COLLECTION_SEED],
+        bump = collection.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub collection: Account<'info, Collection>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [FOREIGN_EMITTER_SEED, chain.to_le_bytes().as_ref()],
+        bump = foreign_emitter.bump
+    )]
+    pub foreign_emitter: Account<'info, ForeignEmitter>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(vaa_hash: [u8; 32])]
+pub struct ReleaseCrossChainNft<'info> {
+    #[account(seeds = [COLLECTION_SEED], bump = collection.bump)]
+    pub collection: Account<'info, Collection>,
+
+    /// CHECK: The core bridge configured at `initialize`
+    #[account(
+        executable,
+        address = collection.wormhole_program_id @ ErrorCode::InvalidWormholeProgram
+    )]
+    pub wormhole_program: UncheckedAccount<'info>,
+
+    /// CHECK: Guardian-verified VAA, see `ReceiveCrossChainMint`
+    #[account(
+        seeds = [WORMHOLE_POSTED_VAA_SEED, vaa_hash.as_ref()],
+        bump,
+        seeds::program = wormhole_program.key(),
+        owner = wormhole_program.key() @ ErrorCode::InvalidPostedVaa
+    )]
+    pub posted_vaa: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [FOREIGN_EMITTER_SEED, foreign_emitter.chain.to_le_bytes().as_ref()],
+        bump = foreign_emitter.bump
+    )]
+    pub foreign_emitter: Account<'info, ForeignEmitter>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ClaimedVaa::INIT_SPACE,
+        seeds = [CLAIMED_VAA_SEED, vaa_hash.as_ref()],
+        bump
+    )]
+    pub claimed_vaa: Account<'info, ClaimedVaa>,
+
+    #[account(
+        mut,
+        seeds = [
+            UNIQUE_ID_SEED,
+            PostedMessageData::load_posted_vaa(&posted_vaa)?.mint_payload()?.unique_id.as_ref()
+        ],
+        bump = unique_id_record.bump,
+        constraint = unique_id_record.cross_chain @ ErrorCode::NotBridgedOut
+    )]
+    pub unique_id_record: Account<'info, UniqueIdRecord>,
+
+    #[account(address = unique_id_record.mint)]
+    pub mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = collection,
+    )]
+    pub escrow_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Wallet named in the payload, checked by the handler
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+    )]
+    pub recipient_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Relayer, pays for every account created here
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(vaa_hash: [u8; 32])]
+pub struct ReceiveCrossChainMint<'info> {
+    #[account(mut, seeds = [COLLECTION_SEED], bump = collection.bump)]
+    pub collection: Box<Account<'info, Collection>>,
+
+    /// CHECK: The core bridge configured at `initialize`
+    #[account(
+        executable,
+        address = collection.wormhole_program_id @ ErrorCode::InvalidWormholeProgram
+    )]
+    pub wormhole_program: UncheckedAccount<'info>,
+
+    /// CHECK: Guardian-verified VAA. Only the core bridge can create an
+    /// account at this address, and only after checking the signatures
+    #[account(
+        seeds = [WORMHOLE_POSTED_VAA_SEED, vaa_hash.as_ref()],
+        bump,
+        seeds::program = wormhole_program.key(),
+        owner = wormhole_program.key() @ ErrorCode::InvalidPostedVaa
+    )]
+    pub posted_vaa: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [FOREIGN_EMITTER_SEED, foreign_emitter.chain.to_le_bytes().as_ref()],
+        bump = foreign_emitter.bump
+    )]
+    pub foreign_emitter: Account<'info, ForeignEmitter>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ClaimedVaa::INIT_SPACE,
+        seeds = [CLAIMED_VAA_SEED, vaa_hash.as_ref()],
+        bump
+    )]
+    pub claimed_vaa: Account<'info, ClaimedVaa>,
+
+    /// CHECK: Wallet named in the payload, checked by the handler
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        mint::decimals = 0,
+        mint::authority = collection,
+        mint::freeze_authority = collection,
+    )]
+    pub mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + UniqueIdRecord::INIT_SPACE,
+        seeds = [
+            UNIQUE_ID_SEED,
+            PostedMessageData::load_posted_vaa(&posted_vaa)?.mint_payload()?.unique_id.as_ref()
+        ],
+        bump
+    )]
+    pub unique_id_record: Box<Account<'info, UniqueIdRecord>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + TokenIdRecord::INIT_SPACE,
+        seeds = [TOKEN_ID_SEED, collection.next_token_id().to_le_bytes().as_ref()],
+        bump
+    )]
+    pub token_id_record: Box<Account<'info, TokenIdRecord>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MintRecord::INIT_SPACE,
+        seeds = [MINT_RECORD_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub mint_record: Box<Account<'info, MintRecord>>,
+
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+    )]
+    pub token_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Metaplex metadata PDA for `mint`, created by the metadata program
+    #[account(
+        mut,
+        seeds = [b"metadata", metadata_program.key().as_ref(), mint.key().as_ref()],
+        bump,
+        seeds::program = metadata_program.key()
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Metaplex master edition PDA for `mint`, created by the metadata program
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            mint.key().as_ref(),
+            b"edition"
+        ],
+        bump,
+        seeds::program = metadata_program.key()
+    )]
+    pub master_edition: UncheckedAccount<'info>,
+
+    /// CHECK: The collection NFT's mint, created at `initialize`
+    #[account(seeds = [COLLECTION_MINT_SEED], bump)]
+    pub collection_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Metaplex metadata PDA for `collection_mint`, its size is updated
+    #[account(
+        mut,
+        seeds = [b"metadata", metadata_program.key().as_ref(), collection_mint.key().as_ref()],
+        bump,
+        seeds::program = metadata_program.key()
+    )]
+    pub collection_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Metaplex master edition PDA for `collection_mint`
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            collection_mint.key().as_ref(),
+            b"edition"
+        ],
+        bump,
+        seeds::program = metadata_program.key()
+    )]
+    pub collection_master_edition: UncheckedAccount<'info>,
+
+    /// Relayer, pays for every account created here
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
 #[derive(Accounts)]
 pub struct GetNonce<'info> {
     pub user_state: Account<'info, UserState>,
 }
 
 #[derive(Accounts)]
+#[instruction(unique_id: [u8; 32])]
 pub struct CheckUniqueId<'info> {
-    pub collection: Account<'info, Collection>,
+    /// CHECK: May not exist; whether it does is the answer
+    #[account(seeds = [UNIQUE_ID_SEED, unique_id.as_ref()], bump)]
+    pub unique_id_record: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
+#[instruction(unique_id: [u8; 32])]
 pub struct GetTokenByUniqueId<'info> {
-    pub collection: Account<'info, Collection>,
+    /// CHECK: Deserialized by the handler, which reports a missing record
+    #[account(seeds = [UNIQUE_ID_SEED, unique_id.as_ref()], bump)]
+    pub unique_id_record: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
+#[instruction(token_id: u64)]
 pub struct GetUniqueIdByToken<'info> {
+    /// CHECK: Deserialized by the handler, which reports a missing record
+    #[account(seeds = [TOKEN_ID_SEED, token_id.to_le_bytes().as_ref()], bump)]
+    pub token_id_record: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(unique_id: [u8; 32], token_id: u64)]
+pub struct MigrateRegistryEntry<'info> {
+    #[account(
+        mut,
+        seeds = [COLLECTION_SEED],
+        bump = collection.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
     pub collection: Account<'info, Collection>,
+
+    /// CHECK: Matched against the legacy mint mapping by the handler
+    pub mint: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + UniqueIdRecord::INIT_SPACE,
+        seeds = [UNIQUE_ID_SEED, unique_id.as_ref()],
+        bump
+    )]
+    pub unique_id_record: Account<'info, UniqueIdRecord>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + TokenIdRecord::INIT_SPACE,
+        seeds = [TOKEN_ID_SEED, token_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub token_id_record: Account<'info, TokenIdRecord>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MintRecord::INIT_SPACE,
+        seeds = [MINT_RECORD_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub mint_record: Account<'info, MintRecord>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinishRegistryMigration<'info> {
+    #[account(
+        mut,
+        seeds = [COLLECTION_SEED],
+        bump = collection.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub collection: Account<'info, Collection>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
@@ -379,26 +1422,52 @@ pub struct PublishMessage<'info> {
     pub rent: AccountInfo<'info>,
 }
 
-#[derive(Clone)]
-pub struct WormholeProgram;
-
-impl anchor_lang::Id for WormholeProgram {
-    fn id() -> Pubkey {
-        // Wormhole program ID on Solana mainnet
-        "11111111111111111111111111111111".parse().unwrap()
-        // "worm2ZoG2kUd4vFXhvjh93UUH596ayRfgQ2MgjNMTth"
-        //     .parse()
-        //     .unwrap()
-    }
+/// post_message arguments, after the `WORMHOLE_POST_MESSAGE_IX` byte.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct PostMessageData {
+    pub nonce: u32,
+    pub payload: Vec<u8>,
+    pub consistency_level: u8,
 }
 
+/// Core bridge message account data after its prefix, `b"msg"` for posted
+/// messages and `WORMHOLE_POSTED_VAA_PREFIX` for posted VAAs. Plain borsh.
 #[derive(AnchorSerialize, AnchorDeserialize)]
-pub enum WormholeInstruction {
-    PostMessage {
-        nonce: u32,
-        payload: Vec<u8>,
-        consistency_level: u8,
-    },
+pub struct PostedMessageData {
+    pub vaa_version: u8,
+    pub consistency_level: u8,
+    pub vaa_time: u32,
+    pub vaa_signature_account: Pubkey,
+    pub submission_time: u32,
+    pub nonce: u32,
+    pub sequence: u64,
+    pub emitter_chain: u16,
+    pub emitter_address: [u8; 32],
+    pub payload: Vec<u8>,
+}
+
+impl PostedMessageData {
+    /// Reads a posted VAA. Its address and owner are checked by the accounts
+    /// struct, this only checks the layout.
+    pub fn load_posted_vaa(info: &AccountInfo) -> Result<Self> {
+        let data = info.try_borrow_data()?;
+        let body = data
+            .strip_prefix(WORMHOLE_POSTED_VAA_PREFIX)
+            .ok_or(ErrorCode::InvalidPostedVaa)?;
+        Self::deserialize(&mut &body[..]).map_err(|_| error!(ErrorCode::InvalidPostedVaa))
+    }
+
+    pub fn message(&self) -> Result<Message> {
+        Message::decode(&self.payload)
+    }
+
+    /// The NFT transfer in a mint or position message.
+    pub fn mint_payload(&self) -> Result<MintRequest> {
+        self.message()?
+            .mint_request()
+            .cloned()
+            .ok_or(error!(ErrorCode::InvalidPayload))
+    }
 }
 
 // ================================
@@ -425,35 +1494,101 @@ pub mod unique_low {
         collection.total_supply = 0;
         collection.wormhole_program_id = wormhole_program_id;
         collection.bump = ctx.bumps.collection;
+
+        // The collection NFT: a sized Metaplex collection whose master
+        // edition locks supply at 1. Items are verified into it at mint.
+        let data = collection.collection_metadata()?;
+        let collection_seeds = &[COLLECTION_SEED, &[collection.bump]];
+        let signer = &[&collection_seeds[..]];
+
+        mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.collection_mint.to_account_info(),
+                    to: ctx.accounts.collection_token.to_account_info(),
+                    authority: collection.to_account_info(),
+                },
+                signer,
+            ),
+            1,
+        )?;
+
+        create_metadata_accounts_v3(
+            CpiContext::new_with_signer(
+                ctx.accounts.metadata_program.to_account_info(),
+                CreateMetadataAccountsV3 {
+                    metadata: ctx.accounts.collection_metadata.to_account_info(),
+                    mint: ctx.accounts.collection_mint.to_account_info(),
+                    mint_authority: collection.to_account_info(),
+                    payer: ctx.accounts.authority.to_account_info(),
+                    update_authority: collection.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+                signer,
+            ),
+            data,
+            true,
+            true,
+            Some(CollectionDetails::V1 { size: 0 }),
+        )?;
+
+        create_master_edition_v3(
+            CpiContext::new_with_signer(
+                ctx.accounts.metadata_program.to_account_info(),
+                CreateMasterEditionV3 {
+                    edition: ctx.accounts.collection_master_edition.to_account_info(),
+                    mint: ctx.accounts.collection_mint.to_account_info(),
+                    update_authority: collection.to_account_info(),
+                    mint_authority: collection.to_account_info(),
+                    payer: ctx.accounts.authority.to_account_info(),
+                    metadata: ctx.accounts.collection_metadata.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+                signer,
+            ),
+            Some(0),
+        )?;
         Ok(())
     }
 
+    /// Bare SPL mint without Metaplex accounts. These NFTs are not verified
+    /// members of the collection NFT, so `simple_vault` does not accept them.
     pub fn mint_nft(ctx: Context<MintNft>) -> Result<()> {
         let collection = &mut ctx.accounts.collection;
         let user_state = &mut ctx.accounts.user_state;
 
-        // Generate unique ID
-        let chain_id = 1u64;
-        let unique_id = generate_unique_id(chain_id, &ctx.accounts.user.key(), user_state.nonce)?;
-
-        /// CHECK:
-        // Check if unique ID already exists
-        require!(
-            !collection.unique_id_exists(unique_id),
-            ErrorCode::UniqueIdAlreadyExists
-        );
+        // The record PDAs are seeded by this unique ID and token ID, so their
+        // `init` already rejected a duplicate
+        let unique_id = user_state.next_unique_id(&ctx.accounts.user.key());
+        let token_id = collection.next_token_id();
 
         // Increment counters
-        collection.total_supply += 1;
+        collection.total_supply = token_id;
         user_state.nonce += 1;
 
-        let token_id = collection.total_supply;
-
         // Store mappings
-        collection.add_unique_id_mapping(unique_id, token_id);
-        collection.mint_to_unique_id.push(MintToUniqueId {
-            mint: ctx.accounts.mint.key(),
+        let mint = ctx.accounts.mint.key();
+        ctx.accounts.unique_id_record.set_inner(UniqueIdRecord {
+            unique_id,
+            token_id,
+            mint,
+            cross_chain: false,
+            bump: ctx.bumps.unique_id_record,
+        });
+        ctx.accounts.token_id_record.set_inner(TokenIdRecord {
+            token_id,
+            unique_id,
+            bump: ctx.bumps.token_id_record,
+        });
+        ctx.accounts.mint_record.set_inner(MintRecord {
+            mint,
             unique_id,
+            token_id,
+            bump: ctx.bumps.mint_record,
         });
 
         let collection_seeds = &[COLLECTION_SEED, &[collection.bump]];
@@ -485,83 +1620,249 @@ pub mod unique_low {
         Ok(())
     }
 
+    /// `mint_nft` plus Metaplex metadata with the collection PDA as update
+    /// authority, a master edition that locks supply at 1 and takes over the
+    /// mint authority, and verified membership of the collection NFT. Only
+    /// these mints can pass `simple_vault`'s collection check.
+    pub fn mint_nft_with_metadata(ctx: Context<MintNftWithMetadata>) -> Result<()> {
+        let collection = &mut ctx.accounts.collection;
+        let user_state = &mut ctx.accounts.user_state;
+
+        // The record PDAs are seeded by this unique ID and token ID, so their
+        // `init` already rejected a duplicate
+        let unique_id = user_state.next_unique_id(&ctx.accounts.user.key());
+        let token_id = collection.next_token_id();
+        let data = collection.metadata_for(token_id)?;
+
+        // Increment counters
+        collection.total_supply = token_id;
+        user_state.nonce += 1;
+
+        // Store mappings
+        let mint = ctx.accounts.mint.key();
+        ctx.accounts.unique_id_record.set_inner(UniqueIdRecord {
+            unique_id,
+            token_id,
+            mint,
+            cross_chain: false,
+            bump: ctx.bumps.unique_id_record,
+        });
+        ctx.accounts.token_id_record.set_inner(TokenIdRecord {
+            token_id,
+            unique_id,
+            bump: ctx.bumps.token_id_record,
+        });
+        ctx.accounts.mint_record.set_inner(MintRecord {
+            mint,
+            unique_id,
+            token_id,
+            bump: ctx.bumps.mint_record,
+        });
+
+        CollectionItem {
+            collection: collection.to_account_info(),
+            collection_bump: collection.bump,
+            mint: ctx.accounts.mint.to_account_info(),
+            token_account: ctx.accounts.token_account.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            master_edition: ctx.accounts.master_edition.to_account_info(),
+            collection_mint: ctx.accounts.collection_mint.to_account_info(),
+            collection_metadata: ctx.accounts.collection_metadata.to_account_info(),
+            collection_master_edition: ctx.accounts.collection_master_edition.to_account_info(),
+            payer: ctx.accounts.user.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            metadata_program: ctx.accounts.metadata_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            rent: ctx.accounts.rent.to_account_info(),
+        }
+        .mint(data)?;
+
+        emit!(NftMinted {
+            user: ctx.accounts.user.key(),
+            token_id,
+            unique_id,
+            nonce: user_state.nonce - 1,
+        });
+
+        Ok(())
+    }
+
     pub fn request_cross_chain_mint(
         ctx: Context<RequestCrossChainMint>,
         nonce: u32,
         target_chain_id: u16,
         recipient: [u8; 32], // 32-byte recipient address for Ethereum
     ) -> Result<()> {
-        let collection = &mut ctx.accounts.collection;
+        ctx.accounts
+            .send(&ctx.bumps, nonce, target_chain_id, recipient, None)
+    }
 
-        // Verify token ownership by checking token account
-        require!(
-            ctx.accounts.token_account.amount == 1,
-            ErrorCode::NotTokenOwner
-        );
-        require!(
-            ctx.accounts.token_account.owner == ctx.accounts.user.key(),
-            ErrorCode::NotTokenOwner
-        );
+    /// `request_cross_chain_mint` for an NFT backing a simple_vault position,
+    /// which travels in the same message. Only simple_vault can call this.
+    pub fn request_cross_chain_transfer(
+        ctx: Context<RequestCrossChainTransfer>,
+        nonce: u32,
+        target_chain_id: u16,
+        recipient: [u8; 32],
+        position: PositionSnapshot,
+    ) -> Result<()> {
+        ctx.accounts.request.send(
+            &ctx.bumps.request,
+            nonce,
+            target_chain_id,
+            recipient,
+            Some(position),
+        )
+    }
 
-        // Get unique_id from mint account (derive from collection mappings)
-        let unique_id = collection.get_unique_id_by_mint(&ctx.accounts.mint.key())?;
+    /// Trusts `address` as the cross-chain mint contract on `chain`,
+    /// replacing any previous emitter for that chain.
+    pub fn register_emitter(
+        ctx: Context<RegisterEmitter>,
+        chain: u16,
+        address: [u8; 32],
+    ) -> Result<()> {
+        foreign_chain(chain)?;
+        require!(address != [0; 32], ErrorCode::InvalidEmitterAddress);
+        ctx.accounts.foreign_emitter.set_inner(ForeignEmitter {
+            chain,
+            address,
+            bump: ctx.bumps.foreign_emitter,
+        });
 
-        // Create payload for Wormhole (same format as Ethereum)
-        // abi.encode(recipient, uniqueId, targetChainId)
-        let mut payload = Vec::new();
-        payload.extend_from_slice(&recipient); // 32 bytes
-        payload.extend_from_slice(&unique_id); // 32 bytes
-        payload.extend_from_slice(&target_chain_id.to_be_bytes()); // 2 bytes
+        emit!(ForeignEmitterRegistered { chain, address });
+        Ok(())
+    }
 
-        // Call Wormhole to publish message
-        let wormhole_accounts = PublishMessage {
-            bridge: ctx.accounts.wormhole_bridge.to_account_info(),
-            message: ctx.accounts.wormhole_message.to_account_info(),
-            emitter: ctx.accounts.wormhole_emitter.to_account_info(),
-            sequence: ctx.accounts.wormhole_sequence.to_account_info(),
-            payer: ctx.accounts.user.to_account_info(),
-            fee_collector: ctx.accounts.wormhole_fee_collector.to_account_info(),
-            clock: ctx.accounts.clock.to_account_info(),
-            system_program: ctx.accounts.system_program.to_account_info(),
-            rent: ctx.accounts.rent.to_account_info(),
-        };
+    /// Stops trusting `chain`'s emitter. Outbound mints to `chain` are
+    /// refused until an emitter is registered again.
+    pub fn deregister_emitter(ctx: Context<DeregisterEmitter>, _chain: u16) -> Result<()> {
+        let emitter = &ctx.accounts.foreign_emitter;
+        emit!(ForeignEmitterDeregistered {
+            chain: emitter.chain,
+            address: emitter.address,
+        });
+        Ok(())
+    }
 
-        let collection_key = collection.key();
-        let collection_bump = collection.bump;
-        // Use program as emitter authority
-        let collection_seeds = &[COLLECTION_SEED, collection_key.as_ref(), &[collection_bump]];
-        let signer = &[&collection_seeds[..]];
+    /// Redeems a cross-chain mint VAA from a registered emitter: mints the
+    /// recipient a verified collection NFT that keeps the foreign `unique_id`.
+    /// Anyone may relay it, the `ClaimedVaa` PDA makes it single-use.
+    pub fn receive_cross_chain_mint(
+        ctx: Context<ReceiveCrossChainMint>,
+        _vaa_hash: [u8; 32],
+    ) -> Result<()> {
+        let (vaa, payload) = verify_mint_vaa(
+            &ctx.accounts.posted_vaa,
+            &ctx.accounts.foreign_emitter,
+            &ctx.accounts.recipient.key(),
+        )?;
+
+        ctx.accounts.claimed_vaa.set_inner(ClaimedVaa {
+            emitter_chain: vaa.emitter_chain,
+            sequence: vaa.sequence,
+            bump: ctx.bumps.claimed_vaa,
+        });
 
-        let wormhole_ctx = CpiContext::new_with_signer(
-            ctx.accounts.wormhole_program.to_account_info(),
-            wormhole_accounts,
-            signer,
-        );
+        let collection = &mut ctx.accounts.collection;
+        let token_id = collection.next_token_id();
+        let data = collection.metadata_for(token_id)?;
+        collection.total_supply = token_id;
+
+        // The identity is new to Solana, so there is nothing in escrow
+        let unique_id = payload.unique_id;
+        let mint = ctx.accounts.mint.key();
+        ctx.accounts.unique_id_record.set_inner(UniqueIdRecord {
+            unique_id,
+            token_id,
+            mint,
+            cross_chain: false,
+            bump: ctx.bumps.unique_id_record,
+        });
+        ctx.accounts.token_id_record.set_inner(TokenIdRecord {
+            token_id,
+            unique_id,
+            bump: ctx.bumps.token_id_record,
+        });
+        ctx.accounts.mint_record.set_inner(MintRecord {
+            mint,
+            unique_id,
+            token_id,
+            bump: ctx.bumps.mint_record,
+        });
 
-        // Consistency level 1 (same as Ethereum contract)
-        let consistency_level = 1u8;
+        CollectionItem {
+            collection: collection.to_account_info(),
+            collection_bump: collection.bump,
+            mint: ctx.accounts.mint.to_account_info(),
+            token_account: ctx.accounts.token_account.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            master_edition: ctx.accounts.master_edition.to_account_info(),
+            collection_mint: ctx.accounts.collection_mint.to_account_info(),
+            collection_metadata: ctx.accounts.collection_metadata.to_account_info(),
+            collection_master_edition: ctx.accounts.collection_master_edition.to_account_info(),
+            payer: ctx.accounts.payer.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            metadata_program: ctx.accounts.metadata_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            rent: ctx.accounts.rent.to_account_info(),
+        }
+        .mint(data)?;
 
-        // This would call Wormhole's publish_message
-        // Note: Actual Wormhole integration requires specific account setup
-        publish_message(wormhole_ctx, nonce, payload, consistency_level)?;
+        emit!(CrossChainMintReceived {
+            recipient: ctx.accounts.recipient.key(),
+            mint,
+            unique_id,
+            token_id,
+            emitter_chain: vaa.emitter_chain,
+            sequence: vaa.sequence,
+        });
 
-        // Mark as cross-chain in collection state
-        collection.mark_cross_chain(unique_id);
+        Ok(())
+    }
 
-        // Get sequence number from Wormhole (would be returned from actual call)
-        let sequence_account_data = ctx.accounts.wormhole_sequence.try_borrow_data()?;
-        let sequence = u64::from_le_bytes(
-            sequence_account_data[0..8]
-                .try_into()
-                .map_err(|_| error!(ErrorCode::InvalidSequenceData))?,
-        );
+    /// Redeems a cross-chain mint or position VAA for an identity that left
+    /// Solana through `request_cross_chain_mint` or
+    /// `request_cross_chain_transfer`: the escrowed NFT goes to the recipient
+    /// instead of a new one being minted.
+    pub fn release_cross_chain_nft(
+        ctx: Context<ReleaseCrossChainNft>,
+        _vaa_hash: [u8; 32],
+    ) -> Result<()> {
+        let (vaa, payload) = verify_release_vaa(
+            &ctx.accounts.posted_vaa,
+            &ctx.accounts.foreign_emitter,
+            &ctx.accounts.recipient.key(),
+        )?;
+
+        ctx.accounts.claimed_vaa.set_inner(ClaimedVaa {
+            emitter_chain: vaa.emitter_chain,
+            sequence: vaa.sequence,
+            bump: ctx.bumps.claimed_vaa,
+        });
+        ctx.accounts.unique_id_record.cross_chain = false;
 
-        emit!(CrossChainMintRequested {
-            sender: ctx.accounts.user.key(),
+        let collection_seeds = &[COLLECTION_SEED, &[ctx.accounts.collection.bump]];
+        let signer = &[&collection_seeds[..]];
+        token_transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TokenTransfer {
+                    from: ctx.accounts.escrow_token_account.to_account_info(),
+                    to: ctx.accounts.recipient_token_account.to_account_info(),
+                    authority: ctx.accounts.collection.to_account_info(),
+                },
+                signer,
+            ),
+            1,
+        )?;
+
+        emit!(CrossChainNftReleased {
+            recipient: ctx.accounts.recipient.key(),
             mint: ctx.accounts.mint.key(),
-            unique_id,
-            target_chain_id,
-            sequence,
+            unique_id: payload.unique_id,
+            emitter_chain: vaa.emitter_chain,
+            sequence: vaa.sequence,
         });
 
         Ok(())
@@ -571,22 +1872,93 @@ pub mod unique_low {
         Ok(ctx.accounts.user_state.nonce)
     }
 
-    pub fn unique_id_exists(ctx: Context<CheckUniqueId>, unique_id: [u8; 32]) -> Result<bool> {
-        Ok(ctx.accounts.collection.unique_id_exists(unique_id))
+    pub fn unique_id_exists(ctx: Context<CheckUniqueId>, _unique_id: [u8; 32]) -> Result<bool> {
+        Ok(record_exists(&ctx.accounts.unique_id_record))
     }
 
     pub fn get_token_id_by_unique_id(
         ctx: Context<GetTokenByUniqueId>,
         unique_id: [u8; 32],
     ) -> Result<u64> {
-        ctx.accounts.collection.get_token_id_by_unique_id(unique_id)
+        let record: UniqueIdRecord =
+            load_record(&ctx.accounts.unique_id_record, ErrorCode::UniqueIdNotFound)?;
+        require!(record.unique_id == unique_id, ErrorCode::UniqueIdNotFound);
+        Ok(record.token_id)
     }
 
     pub fn get_unique_id_by_token_id(
         ctx: Context<GetUniqueIdByToken>,
         token_id: u64,
     ) -> Result<[u8; 32]> {
-        ctx.accounts.collection.get_unique_id_by_token_id(token_id)
+        let record: TokenIdRecord =
+            load_record(&ctx.accounts.token_id_record, ErrorCode::TokenIdNotFound)?;
+        require!(record.token_id == token_id, ErrorCode::TokenIdNotFound);
+        Ok(record.unique_id)
+    }
+
+    /// Moves one NFT from a legacy collection's Vecs into its record PDAs.
+    /// Run once per minted NFT, then `finish_registry_migration`.
+    pub fn migrate_registry_entry(
+        ctx: Context<MigrateRegistryEntry>,
+        unique_id: [u8; 32],
+        token_id: u64,
+    ) -> Result<()> {
+        let collection_info = ctx.accounts.collection.to_account_info();
+        let mut legacy =
+            LegacyCollection::load(&collection_info)?.ok_or(error!(ErrorCode::UniqueIdNotFound))?;
+
+        let mint = ctx.accounts.mint.key();
+        let cross_chain = legacy.take_entry(unique_id, token_id, &mint)?;
+        legacy.store(&collection_info)?;
+
+        ctx.accounts.unique_id_record.set_inner(UniqueIdRecord {
+            unique_id,
+            token_id,
+            mint,
+            cross_chain,
+            bump: ctx.bumps.unique_id_record,
+        });
+        ctx.accounts.token_id_record.set_inner(TokenIdRecord {
+            token_id,
+            unique_id,
+            bump: ctx.bumps.token_id_record,
+        });
+        ctx.accounts.mint_record.set_inner(MintRecord {
+            mint,
+            unique_id,
+            token_id,
+            bump: ctx.bumps.mint_record,
+        });
+
+        emit!(RegistryEntryMigrated {
+            unique_id,
+            token_id,
+            mint,
+            cross_chain,
+        });
+        Ok(())
+    }
+
+    /// Drops the emptied legacy Vecs from the collection account and returns
+    /// the freed rent to the authority.
+    pub fn finish_registry_migration(ctx: Context<FinishRegistryMigration>) -> Result<()> {
+        let collection_info = ctx.accounts.collection.to_account_info();
+        if let Some(legacy) = LegacyCollection::load(&collection_info)? {
+            require!(legacy.is_empty(), ErrorCode::RegistryMigrationPending);
+        }
+
+        let new_len = 8 + Collection::INIT_SPACE;
+        if collection_info.data_len() <= new_len {
+            return Ok(());
+        }
+        collection_info.resize(new_len)?;
+
+        let excess = collection_info
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(new_len));
+        **collection_info.try_borrow_mut_lamports()? -= excess;
+        **ctx.accounts.authority.try_borrow_mut_lamports()? += excess;
+        Ok(())
     }
 
     pub fn total_supply(ctx: Context<GetTotalSupply>) -> Result<u64> {
@@ -599,27 +1971,140 @@ pub mod unique_low {
 // ================================
 
 // Same unique ID generation as Ethereum: keccak256(chainId, wallet, nonce)
-fn generate_unique_id(chain_id: u64, wallet: &Pubkey, nonce: u64) -> Result<[u8; 32]> {
+pub fn generate_unique_id(chain_id: u64, wallet: &Pubkey, nonce: u64) -> [u8; 32] {
     let mut data = Vec::new();
     data.extend_from_slice(&chain_id.to_be_bytes()); // Big endian like Solidity
     data.extend_from_slice(&wallet.to_bytes());
     data.extend_from_slice(&nonce.to_be_bytes());
-    Ok(hash(&data).to_bytes())
+    hash(&data).to_bytes()
+}
+
+/// Checks a posted cross-chain mint VAA against the registered emitter for
+/// its chain, and that it is a `MintRequest` addressed to `recipient` on
+/// Solana. A `PositionTransfer` is refused: its NFT already exists here.
+pub fn verify_mint_vaa(
+    posted_vaa: &AccountInfo,
+    emitter: &ForeignEmitter,
+    recipient: &Pubkey,
+) -> Result<(PostedMessageData, MintRequest)> {
+    let (vaa, message) = verify_inbound_vaa(posted_vaa, emitter)?;
+    let Message::MintRequest(payload) = message else {
+        return err!(ErrorCode::InvalidPayload);
+    };
+    check_recipient(&payload, recipient)?;
+    Ok((vaa, payload))
+}
+
+/// Like `verify_mint_vaa`, but also accepts a `PositionTransfer`. An NFT
+/// coming home with its position leaves escrow on the same VAA that
+/// simple_vault's `receive_position` redeems for the shares. One that comes
+/// home on a `MintRequest` gets its position back through `reclaim_position`.
+pub fn verify_release_vaa(
+    posted_vaa: &AccountInfo,
+    emitter: &ForeignEmitter,
+    recipient: &Pubkey,
+) -> Result<(PostedMessageData, MintRequest)> {
+    let (vaa, message) = verify_inbound_vaa(posted_vaa, emitter)?;
+    let payload = match message {
+        Message::MintRequest(mint) => mint,
+        Message::PositionTransfer(transfer) => transfer.mint,
+        Message::Ack(_) => return err!(ErrorCode::InvalidPayload),
+    };
+    check_recipient(&payload, recipient)?;
+    Ok((vaa, payload))
 }
 
-// Placeholder for Wormhole CPI call
+fn check_recipient(payload: &MintRequest, recipient: &Pubkey) -> Result<()> {
+    require_keys_eq!(
+        *recipient,
+        Pubkey::new_from_array(payload.recipient),
+        ErrorCode::RecipientMismatch
+    );
+    Ok(())
+}
+
+/// Checks a posted mint or position VAA against the registered emitter for
+/// its chain, and that it is addressed to Solana.
+pub fn verify_inbound_vaa(
+    posted_vaa: &AccountInfo,
+    emitter: &ForeignEmitter,
+) -> Result<(PostedMessageData, Message)> {
+    let vaa = PostedMessageData::load_posted_vaa(posted_vaa)?;
+    require!(
+        vaa.emitter_chain == emitter.chain && vaa.emitter_address == emitter.address,
+        ErrorCode::UnknownEmitter
+    );
+
+    let message = vaa.message()?;
+    let mint = message
+        .mint_request()
+        .ok_or(error!(ErrorCode::InvalidPayload))?;
+    require!(
+        Chain::from(mint.target_chain_id) == Chain::Solana,
+        ErrorCode::WrongTargetChain
+    );
+    Ok((vaa, message))
+}
+
+/// `chain_id` as a Wormhole chain this collection can bridge to: a known
+/// chain other than Solana itself.
+pub fn foreign_chain(chain_id: u16) -> Result<Chain> {
+    match Chain::from(chain_id) {
+        Chain::Any | Chain::Solana | Chain::Unknown(_) => err!(ErrorCode::UnsupportedChain),
+        chain => Ok(chain),
+    }
+}
+
+/// The core bridge's `fee` from its `Bridge` config account (plain borsh:
+/// guardian_set_index u32, last_lamports u64, guardian_set_expiration_time
+/// u32, fee u64).
+pub fn wormhole_bridge_fee(bridge: &AccountInfo) -> Result<u64> {
+    let data = bridge.try_borrow_data()?;
+    let fee = data
+        .get(16..24)
+        .ok_or(ErrorCode::InvalidBridgeData)?
+        .try_into()
+        .map_err(|_| error!(ErrorCode::InvalidBridgeData))?;
+    Ok(u64::from_le_bytes(fee))
+}
+
+/// Next sequence the core bridge will assign to the emitter. The tracker
+/// only exists after the emitter's first message.
+pub fn wormhole_sequence_value(sequence: &AccountInfo) -> Result<u64> {
+    let data = sequence.try_borrow_data()?;
+    if data.is_empty() {
+        return Ok(0);
+    }
+    let value = data
+        .get(0..8)
+        .ok_or(ErrorCode::InvalidSequenceData)?
+        .try_into()
+        .map_err(|_| error!(ErrorCode::InvalidSequenceData))?;
+    Ok(u64::from_le_bytes(value))
+}
+
+/// CPI into the core bridge's post_message. `emitter` and `message` must be
+/// signed for through `ctx.signer_seeds`.
 fn publish_message<'info>(
     ctx: CpiContext<'_, '_, '_, 'info, PublishMessage<'info>>,
     nonce: u32,
     payload: Vec<u8>,
     consistency_level: u8,
 ) -> Result<()> {
+    let mut data = vec![WORMHOLE_POST_MESSAGE_IX];
+    PostMessageData {
+        nonce,
+        payload,
+        consistency_level,
+    }
+    .serialize(&mut data)?;
+
     let ix = Instruction {
-        program_id: WormholeProgram::id(), // wormhole core bridge ID
+        program_id: ctx.program.key(),
         accounts: vec![
-            AccountMeta::new_readonly(ctx.accounts.bridge.key(), false),
-            AccountMeta::new(ctx.accounts.message.key(), false),
-            AccountMeta::new_readonly(ctx.accounts.emitter.key(), false),
+            AccountMeta::new(ctx.accounts.bridge.key(), false),
+            AccountMeta::new(ctx.accounts.message.key(), true),
+            AccountMeta::new_readonly(ctx.accounts.emitter.key(), true),
             AccountMeta::new(ctx.accounts.sequence.key(), false),
             AccountMeta::new(ctx.accounts.payer.key(), true),
             AccountMeta::new(ctx.accounts.fee_collector.key(), false),
@@ -627,12 +2112,7 @@ fn publish_message<'info>(
             AccountMeta::new_readonly(ctx.accounts.rent.key(), false),
             AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),
         ],
-        data: WormholeInstruction::PostMessage {
-            nonce,
-            payload,
-            consistency_level,
-        }
-        .try_to_vec()?,
+        data,
     };
 
     invoke_signed(
@@ -652,4 +2132,281 @@ fn publish_message<'info>(
     )?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legacy_collection() -> LegacyCollection {
+        let entries = [
+            ([1u8; 32], 1u64, Pubkey::new_unique()),
+            ([2u8; 32], 2, Pubkey::new_unique()),
+        ];
+        LegacyCollection {
+            authority: Pubkey::new_unique(),
+            name: "Unique".to_string(),
+            symbol: "UNQ".to_string(),
+            base_uri: "https://example.com/".to_string(),
+            total_supply: 2,
+            wormhole_program_id: Pubkey::new_unique(),
+            bump: 255,
+            unique_id_to_token_id: entries
+                .iter()
+                .map(|&(unique_id, token_id, _)| UniqueIdToTokenId {
+                    unique_id,
+                    token_id,
+                })
+                .collect(),
+            token_id_to_unique_id: entries
+                .iter()
+                .map(|&(unique_id, token_id, _)| TokenIdToUniqueId {
+                    token_id,
+                    unique_id,
+                })
+                .collect(),
+            mint_to_unique_id: entries
+                .iter()
+                .map(|&(unique_id, _, mint)| MintToUniqueId { mint, unique_id })
+                .collect(),
+            cross_chain_unique_ids: vec![[2u8; 32]],
+        }
+    }
+
+    fn account_data(discriminator: &[u8], collection: &LegacyCollection) -> Vec<u8> {
+        let mut data = discriminator.to_vec();
+        collection.serialize(&mut data).unwrap();
+        data
+    }
+
+    fn with_account<T>(data: &mut [u8], f: impl FnOnce(&AccountInfo) -> T) -> T {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let info = AccountInfo::new(&key, false, true, &mut lamports, data, &crate::ID, false, 0);
+        f(&info)
+    }
+
+    #[test]
+    fn test_take_entry_drains_the_legacy_layout() {
+        let collection = legacy_collection();
+        let first_mint = collection.mint_to_unique_id[0].mint;
+        let second_mint = collection.mint_to_unique_id[1].mint;
+        let mut data = account_data(Collection::DISCRIMINATOR, &collection);
+
+        with_account(&mut data, |info| {
+            let mut legacy = LegacyCollection::load(info).unwrap().unwrap();
+            assert!(!legacy.is_empty());
+
+            assert!(!legacy.take_entry([1u8; 32], 1, &first_mint).unwrap());
+            assert!(!legacy.is_empty());
+            legacy.store(info).unwrap();
+
+            let mut legacy = LegacyCollection::load(info).unwrap().unwrap();
+            assert_eq!(legacy.unique_id_to_token_id.len(), 1);
+            assert!(legacy.take_entry([2u8; 32], 2, &second_mint).unwrap());
+            assert!(legacy.is_empty());
+            legacy.store(info).unwrap();
+
+            let legacy = LegacyCollection::load(info).unwrap().unwrap();
+            assert!(legacy.is_empty());
+            assert_eq!(legacy.total_supply, 2);
+        });
+    }
+
+    #[test]
+    fn test_take_entry_rejects_mismatched_mappings() {
+        let mut legacy = legacy_collection();
+        let mint = legacy.mint_to_unique_id[0].mint;
+
+        assert_eq!(
+            legacy.take_entry([3u8; 32], 1, &mint).unwrap_err(),
+            error!(ErrorCode::UniqueIdNotFound)
+        );
+        assert_eq!(
+            legacy.take_entry([1u8; 32], 2, &mint).unwrap_err(),
+            error!(ErrorCode::UniqueIdNotFound)
+        );
+        assert_eq!(
+            legacy
+                .take_entry([1u8; 32], 1, &Pubkey::new_unique())
+                .unwrap_err(),
+            error!(ErrorCode::MintNotFound)
+        );
+        // A failed lookup leaves every mapping in place
+        assert_eq!(legacy.unique_id_to_token_id.len(), 2);
+        assert_eq!(legacy.token_id_to_unique_id.len(), 2);
+        assert_eq!(legacy.mint_to_unique_id.len(), 2);
+    }
+
+    #[test]
+    fn test_load_rejects_other_accounts() {
+        let mut data = account_data(UserState::DISCRIMINATOR, &legacy_collection());
+
+        with_account(&mut data, |info| {
+            assert_eq!(
+                LegacyCollection::load(info).err(),
+                Some(error!(ErrorCode::InvalidCollectionAccount))
+            );
+        });
+    }
+
+    #[test]
+    fn test_load_current_layout() {
+        let legacy = legacy_collection();
+        let collection = Collection {
+            authority: legacy.authority,
+            name: legacy.name,
+            symbol: legacy.symbol,
+            base_uri: legacy.base_uri,
+            total_supply: legacy.total_supply,
+            wormhole_program_id: legacy.wormhole_program_id,
+            bump: legacy.bump,
+        };
+        let mut data = Collection::DISCRIMINATOR.to_vec();
+        collection.serialize(&mut data).unwrap();
+
+        // No room left for the Vecs
+        with_account(&mut data, |info| {
+            assert!(LegacyCollection::load(info).unwrap().is_none());
+        });
+
+        // Trimmed to INIT_SPACE, the zero padding reads as empty Vecs
+        data.resize(8 + Collection::INIT_SPACE, 0);
+        with_account(&mut data, |info| {
+            assert!(LegacyCollection::load(info).unwrap().unwrap().is_empty());
+        });
+    }
+
+    const SOLANA: u16 = 1;
+    const ETHEREUM: u16 = 2;
+
+    fn foreign_emitter() -> ForeignEmitter {
+        ForeignEmitter {
+            chain: ETHEREUM,
+            address: [0x42; 32],
+            bump: 255,
+        }
+    }
+
+    fn mint_request(recipient: &Pubkey, target_chain_id: u16) -> MintRequest {
+        MintRequest {
+            recipient: recipient.to_bytes(),
+            unique_id: [0xab; 32],
+            target_chain_id,
+        }
+    }
+
+    /// A posted VAA account as the mock core bridge's `post_vaa` writes it.
+    fn posted_vaa(emitter_chain: u16, emitter_address: [u8; 32], message: &Message) -> Vec<u8> {
+        let mut data = WORMHOLE_POSTED_VAA_PREFIX.to_vec();
+        PostedMessageData {
+            vaa_version: 1,
+            consistency_level: 1,
+            vaa_time: 1_700_000_000,
+            vaa_signature_account: Pubkey::default(),
+            submission_time: 0,
+            nonce: 0,
+            sequence: 0,
+            emitter_chain,
+            emitter_address,
+            payload: message.encode().unwrap(),
+        }
+        .serialize(&mut data)
+        .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_verify_mint_vaa() {
+        let recipient = Pubkey::new_unique();
+        let emitter = foreign_emitter();
+        let verify = |mut data: Vec<u8>, recipient: &Pubkey| {
+            with_account(&mut data, |info| {
+                verify_mint_vaa(info, &emitter, recipient).err()
+            })
+        };
+        let message = Message::MintRequest(mint_request(&recipient, SOLANA));
+
+        let mut data = posted_vaa(ETHEREUM, emitter.address, &message);
+        with_account(&mut data, |info| {
+            let (vaa, payload) = verify_mint_vaa(info, &emitter, &recipient).unwrap();
+            assert_eq!(vaa.emitter_chain, ETHEREUM);
+            assert_eq!(Message::MintRequest(payload), message);
+        });
+
+        assert_eq!(
+            verify(posted_vaa(ETHEREUM, [0x66; 32], &message), &recipient),
+            Some(error!(ErrorCode::UnknownEmitter))
+        );
+        assert_eq!(
+            verify(
+                posted_vaa(ETHEREUM + 1, emitter.address, &message),
+                &recipient
+            ),
+            Some(error!(ErrorCode::UnknownEmitter))
+        );
+        assert_eq!(
+            verify(
+                posted_vaa(
+                    ETHEREUM,
+                    emitter.address,
+                    &Message::MintRequest(mint_request(&recipient, ETHEREUM))
+                ),
+                &recipient
+            ),
+            Some(error!(ErrorCode::WrongTargetChain))
+        );
+        assert_eq!(
+            verify(
+                posted_vaa(ETHEREUM, emitter.address, &message),
+                &Pubkey::new_unique()
+            ),
+            Some(error!(ErrorCode::RecipientMismatch))
+        );
+    }
+
+    #[test]
+    fn test_only_release_accepts_position_transfers() {
+        let recipient = Pubkey::new_unique();
+        let emitter = foreign_emitter();
+        let mint = mint_request(&recipient, SOLANA);
+        let message = Message::PositionTransfer(PositionTransfer {
+            mint: mint.clone(),
+            position: PositionSnapshot {
+                vault: Pubkey::new_unique(),
+                shares: 1_000_000,
+                assets: 1_000_000,
+                lock_tier: 3,
+                locked_until: 0,
+            },
+        });
+        let mut data = posted_vaa(ETHEREUM, emitter.address, &message);
+
+        with_account(&mut data, |info| {
+            assert_eq!(
+                verify_mint_vaa(info, &emitter, &recipient).err(),
+                Some(error!(ErrorCode::InvalidPayload))
+            );
+            let (_, payload) = verify_release_vaa(info, &emitter, &recipient).unwrap();
+            assert_eq!(payload, mint);
+            assert_eq!(
+                verify_release_vaa(info, &emitter, &Pubkey::new_unique()).err(),
+                Some(error!(ErrorCode::RecipientMismatch))
+            );
+        });
+    }
+
+    #[test]
+    fn test_foreign_chain() {
+        // 0 is `Any` and an unassigned ID is `Unknown`
+        assert_eq!(Chain::from(0), Chain::Any);
+        assert_eq!(Chain::from(u16::MAX), Chain::Unknown(u16::MAX));
+        for chain_id in [0, SOLANA, u16::MAX] {
+            assert_eq!(
+                foreign_chain(chain_id).err(),
+                Some(error!(ErrorCode::UnsupportedChain))
+            );
+        }
+        assert_eq!(foreign_chain(ETHEREUM).unwrap(), Chain::Ethereum);
+    }
+}
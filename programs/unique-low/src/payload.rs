@@ -0,0 +1,414 @@
+use anchor_lang::prelude::*;
+use std::io;
+use wormhole_io::{Readable, Writeable};
+
+use crate::ErrorCode;
+
+// ================================
+// CROSS-CHAIN PAYLOADS
+// ================================
+//
+// Every Wormhole payload the collection sends or accepts is the Solidity
+// `abi.encode` of static types, so the Ethereum contract reads it back with
+// `abi.decode`. Each field takes one 32-byte big-endian word: unsigned
+// integers are zero-padded on the left, `int64` is sign-extended and
+// `bytes32` is copied as is. The first two words are the codec version and
+// the payload type:
+//
+//   MintRequest       abi.encode(uint8 version, uint8 1, bytes32 recipient,
+//                         bytes32 uniqueId, uint16 targetChainId)
+//   PositionTransfer  abi.encode(uint8 version, uint8 2, bytes32 recipient,
+//                         bytes32 uniqueId, uint16 targetChainId,
+//                         bytes32 vault, uint64 shares, uint64 assets,
+//                         uint8 lockTier, int64 lockedUntil)
+//   Ack               abi.encode(uint8 version, uint8 3, bytes32 uniqueId,
+//                         uint64 sequence)
+//
+// Decoding rejects other versions and types, words that are not in their
+// canonical form and trailing bytes.
+
+pub const PAYLOAD_VERSION: u8 = 1;
+pub const PAYLOAD_MINT_REQUEST: u8 = 1;
+pub const PAYLOAD_POSITION_TRANSFER: u8 = 2;
+pub const PAYLOAD_ACK: u8 = 3;
+
+/// Asks the target chain to mint, or release from escrow, the NFT with
+/// `unique_id` to `recipient`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MintRequest {
+    pub recipient: [u8; 32],
+    pub unique_id: [u8; 32],
+    pub target_chain_id: u16,
+}
+
+/// A `MintRequest` for an NFT backing a simple_vault position, which travels
+/// with it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PositionTransfer {
+    pub mint: MintRequest,
+    pub position: PositionSnapshot,
+}
+
+/// A simple_vault position as it leaves its vault. `assets` is the value of
+/// `shares` at that moment.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct PositionSnapshot {
+    pub vault: Pubkey,
+    pub shares: u64,
+    pub assets: u64,
+    pub lock_tier: u8,
+    pub locked_until: i64,
+}
+
+/// Confirms that the message with `sequence`, carrying `unique_id`, was
+/// redeemed by the chain sending the ack.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ack {
+    pub unique_id: [u8; 32],
+    pub sequence: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    MintRequest(MintRequest),
+    PositionTransfer(PositionTransfer),
+    Ack(Ack),
+}
+
+impl Message {
+    pub fn payload_type(&self) -> u8 {
+        match self {
+            Self::MintRequest(_) => PAYLOAD_MINT_REQUEST,
+            Self::PositionTransfer(_) => PAYLOAD_POSITION_TRANSFER,
+            Self::Ack(_) => PAYLOAD_ACK,
+        }
+    }
+
+    /// The NFT transfer the message asks for. Acks carry none.
+    pub fn mint_request(&self) -> Option<&MintRequest> {
+        match self {
+            Self::MintRequest(mint) => Some(mint),
+            Self::PositionTransfer(transfer) => Some(&transfer.mint),
+            Self::Ack(_) => None,
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let mut payload = Vec::new();
+        self.write(&mut payload)?;
+        Ok(payload)
+    }
+
+    pub fn decode(mut bytes: &[u8]) -> Result<Self> {
+        let message = Self::read(&mut bytes).map_err(|_| error!(ErrorCode::InvalidPayload))?;
+        require!(bytes.is_empty(), ErrorCode::InvalidPayload);
+        Ok(message)
+    }
+}
+
+impl Readable for Message {
+    fn read<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        if read_word::<_, u8>(reader)? != PAYLOAD_VERSION {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+        match read_word(reader)? {
+            PAYLOAD_MINT_REQUEST => Ok(Self::MintRequest(Readable::read(reader)?)),
+            PAYLOAD_POSITION_TRANSFER => Ok(Self::PositionTransfer(PositionTransfer {
+                mint: Readable::read(reader)?,
+                position: Readable::read(reader)?,
+            })),
+            PAYLOAD_ACK => Ok(Self::Ack(Readable::read(reader)?)),
+            _ => Err(io::ErrorKind::InvalidData.into()),
+        }
+    }
+}
+
+impl Writeable for Message {
+    fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_word(writer, &PAYLOAD_VERSION)?;
+        write_word(writer, &self.payload_type())?;
+        match self {
+            Self::MintRequest(mint) => mint.write(writer),
+            Self::PositionTransfer(transfer) => {
+                transfer.mint.write(writer)?;
+                transfer.position.write(writer)
+            }
+            Self::Ack(ack) => ack.write(writer),
+        }
+    }
+}
+
+impl Readable for MintRequest {
+    fn read<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        Ok(Self {
+            recipient: read_word(reader)?,
+            unique_id: read_word(reader)?,
+            target_chain_id: read_word(reader)?,
+        })
+    }
+}
+
+impl Writeable for MintRequest {
+    fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_word(writer, &self.recipient)?;
+        write_word(writer, &self.unique_id)?;
+        write_word(writer, &self.target_chain_id)
+    }
+}
+
+impl Readable for PositionSnapshot {
+    fn read<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        Ok(Self {
+            vault: read_word(reader)?,
+            shares: read_word(reader)?,
+            assets: read_word(reader)?,
+            lock_tier: read_word(reader)?,
+            locked_until: read_word(reader)?,
+        })
+    }
+}
+
+impl Writeable for PositionSnapshot {
+    fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_word(writer, &self.vault)?;
+        write_word(writer, &self.shares)?;
+        write_word(writer, &self.assets)?;
+        write_word(writer, &self.lock_tier)?;
+        write_word(writer, &self.locked_until)
+    }
+}
+
+impl Readable for Ack {
+    fn read<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        Ok(Self {
+            unique_id: read_word(reader)?,
+            sequence: read_word(reader)?,
+        })
+    }
+}
+
+impl Writeable for Ack {
+    fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_word(writer, &self.unique_id)?;
+        write_word(writer, &self.sequence)
+    }
+}
+
+/// A static Solidity type, one 32-byte ABI word.
+trait AbiWord: Sized {
+    fn to_word(&self) -> [u8; 32];
+
+    /// `None` unless `word` is exactly what `to_word` produces.
+    fn from_word(word: &[u8; 32]) -> Option<Self>;
+}
+
+macro_rules! impl_abi_word_for_uint {
+    ($($ty:ty),*) => {$(
+        impl AbiWord for $ty {
+            fn to_word(&self) -> [u8; 32] {
+                let mut word = [0; 32];
+                word[32 - std::mem::size_of::<$ty>()..].copy_from_slice(&self.to_be_bytes());
+                word
+            }
+
+            fn from_word(word: &[u8; 32]) -> Option<Self> {
+                let bytes = word[32 - std::mem::size_of::<$ty>()..].try_into().ok()?;
+                let value = <$ty>::from_be_bytes(bytes);
+                (value.to_word() == *word).then_some(value)
+            }
+        }
+    )*};
+}
+
+impl_abi_word_for_uint!(u8, u16, u64);
+
+impl AbiWord for i64 {
+    fn to_word(&self) -> [u8; 32] {
+        let mut word = if *self < 0 { [0xff; 32] } else { [0; 32] };
+        word[24..].copy_from_slice(&self.to_be_bytes());
+        word
+    }
+
+    fn from_word(word: &[u8; 32]) -> Option<Self> {
+        let value = i64::from_be_bytes(word[24..].try_into().ok()?);
+        (value.to_word() == *word).then_some(value)
+    }
+}
+
+impl AbiWord for [u8; 32] {
+    fn to_word(&self) -> [u8; 32] {
+        *self
+    }
+
+    fn from_word(word: &[u8; 32]) -> Option<Self> {
+        Some(*word)
+    }
+}
+
+impl AbiWord for Pubkey {
+    fn to_word(&self) -> [u8; 32] {
+        self.to_bytes()
+    }
+
+    fn from_word(word: &[u8; 32]) -> Option<Self> {
+        Some(Pubkey::new_from_array(*word))
+    }
+}
+
+fn write_word<W: io::Write, T: AbiWord>(writer: &mut W, value: &T) -> io::Result<()> {
+    value.to_word().write(writer)
+}
+
+fn read_word<R: io::Read, T: AbiWord>(reader: &mut R) -> io::Result<T> {
+    let word: [u8; 32] = Readable::read(reader)?;
+    T::from_word(&word).ok_or_else(|| io::ErrorKind::InvalidData.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECIPIENT: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+    const UNIQUE_ID: &str = "abababababababababababababababababababababababababababababababab";
+
+    fn hex_words(words: &[&str]) -> Vec<u8> {
+        words
+            .iter()
+            .flat_map(|word| {
+                assert_eq!(word.len(), 64);
+                (0..64)
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&word[i..i + 2], 16).unwrap())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn bytes32(word: &str) -> [u8; 32] {
+        hex_words(&[word]).try_into().unwrap()
+    }
+
+    fn mint_request(target_chain_id: u16) -> MintRequest {
+        MintRequest {
+            recipient: bytes32(RECIPIENT),
+            unique_id: bytes32(UNIQUE_ID),
+            target_chain_id,
+        }
+    }
+
+    fn position_transfer(locked_until: i64) -> Message {
+        Message::PositionTransfer(PositionTransfer {
+            mint: mint_request(2),
+            position: PositionSnapshot {
+                vault: Pubkey::new_from_array([0x11; 32]),
+                shares: 1_000_000,
+                assets: 1_050_000,
+                lock_tier: 3,
+                locked_until,
+            },
+        })
+    }
+
+    #[test]
+    fn test_mint_request_matches_abi_encode() {
+        // abi.encode(uint8(1), uint8(1), recipient, uniqueId, uint16(10002))
+        let expected = hex_words(&[
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            RECIPIENT,
+            UNIQUE_ID,
+            "0000000000000000000000000000000000000000000000000000000000002712",
+        ]);
+        let message = Message::MintRequest(mint_request(10002));
+
+        assert_eq!(message.encode().unwrap(), expected);
+        assert_eq!(Message::decode(&expected).unwrap(), message);
+    }
+
+    #[test]
+    fn test_position_transfer_matches_abi_encode() {
+        // abi.encode(uint8(1), uint8(2), recipient, uniqueId, uint16(2), vault,
+        //            uint64(1000000), uint64(1050000), uint8(3), int64(1700000000))
+        let expected = hex_words(&[
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            RECIPIENT,
+            UNIQUE_ID,
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "1111111111111111111111111111111111111111111111111111111111111111",
+            "00000000000000000000000000000000000000000000000000000000000f4240",
+            "0000000000000000000000000000000000000000000000000000000000100590",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "000000000000000000000000000000000000000000000000000000006553f100",
+        ]);
+        let message = position_transfer(1_700_000_000);
+
+        assert_eq!(message.encode().unwrap(), expected);
+        assert_eq!(Message::decode(&expected).unwrap(), message);
+        assert_eq!(message.mint_request(), Some(&mint_request(2)));
+    }
+
+    #[test]
+    fn test_negative_lock_time_is_sign_extended() {
+        let message = position_transfer(-86_400);
+        let encoded = message.encode().unwrap();
+
+        assert_eq!(
+            encoded[288..],
+            hex_words(&["fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffeae80"])
+        );
+        assert_eq!(Message::decode(&encoded).unwrap(), message);
+    }
+
+    #[test]
+    fn test_ack_matches_abi_encode() {
+        // abi.encode(uint8(1), uint8(3), uniqueId, uint64(42))
+        let expected = hex_words(&[
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            UNIQUE_ID,
+            "000000000000000000000000000000000000000000000000000000000000002a",
+        ]);
+        let message = Message::Ack(Ack {
+            unique_id: bytes32(UNIQUE_ID),
+            sequence: 42,
+        });
+
+        assert_eq!(message.encode().unwrap(), expected);
+        assert_eq!(Message::decode(&expected).unwrap(), message);
+        assert_eq!(message.mint_request(), None);
+    }
+
+    #[test]
+    fn test_decode_rejects_unknown_version_and_type() {
+        let mut encoded = Message::MintRequest(mint_request(2)).encode().unwrap();
+        encoded[31] = PAYLOAD_VERSION + 1;
+        assert!(Message::decode(&encoded).is_err());
+
+        encoded[31] = PAYLOAD_VERSION;
+        encoded[63] = 4;
+        assert!(Message::decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_non_canonical_words() {
+        let mut dirty_padding = Message::MintRequest(mint_request(2)).encode().unwrap();
+        dirty_padding[128] = 1;
+        assert!(Message::decode(&dirty_padding).is_err());
+
+        let mut bad_sign = position_transfer(-86_400).encode().unwrap();
+        bad_sign[288] = 0;
+        assert!(Message::decode(&bad_sign).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_truncated_and_trailing_bytes() {
+        let encoded = position_transfer(0).encode().unwrap();
+        assert!(Message::decode(&encoded[..encoded.len() - 1]).is_err());
+        assert!(Message::decode(&encoded[..160]).is_err());
+
+        let mut trailing = Message::MintRequest(mint_request(2)).encode().unwrap();
+        trailing.push(0);
+        assert!(Message::decode(&trailing).is_err());
+    }
+}
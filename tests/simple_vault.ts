@@ -12,6 +12,7 @@ import {
 import { SimpleVault } from "../target/types/simple_vault";
 import { UniqueLow } from "../target/types/unique_low";
 import { expect } from "chai";
+import { createHash } from "crypto";
 
 describe("vault-debug following working pattern", () => {
   const provider = anchor.AnchorProvider.env();
@@ -74,10 +75,11 @@ describe("vault-debug following working pattern", () => {
     );
 
     const tx = await nftProgram.methods
-      .mintNft()
+      .mintNftWithMetadata()
       .accounts({
         mint: mint.publicKey,
         user: wallet.publicKey,
+        ...(await registryAccounts(nftProgram, wallet.publicKey)),
       })
       .signers([mint])
       .rpc();
@@ -229,6 +231,7 @@ describe("vault-debug following working pattern", () => {
         .withdraw(sharesToWithdraw)
         .accounts({
           user: wallet.publicKey,
+          recipient: wallet.publicKey,
           vault: REAL_ADDRESSES.VAULT_PDA,
           nftCollection: REAL_ADDRESSES.COLLECTION_PDA,
           userNftToken: testUser.tokenAccount,
@@ -262,4 +265,39 @@ describe("vault-debug following working pattern", () => {
       throw withdrawErr;
     }
   });
-});
\ No newline at end of file
+});
+
+// mint_nft creates registry records seeded by the minter's next unique ID
+// (sha256(chainId, wallet, nonce), big endian) and the next token ID.
+async function registryAccounts(program: Program<UniqueLow>, user: PublicKey) {
+  const [userStatePda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("user_state_v2"), user.toBuffer()],
+    program.programId
+  );
+  const [collectionPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("collection_v2")],
+    program.programId
+  );
+  const userState = await program.account.userState.fetchNullable(userStatePda);
+  const collection = await program.account.collection.fetch(collectionPda);
+
+  const nonce = userState ? userState.nonce : new anchor.BN(0);
+  const uniqueId = createHash("sha256")
+    .update(Buffer.concat([
+      new anchor.BN(1).toArrayLike(Buffer, "be", 8),
+      user.toBuffer(),
+      nonce.toArrayLike(Buffer, "be", 8),
+    ]))
+    .digest();
+  const tokenId = collection.totalSupply.addn(1);
+
+  const [uniqueIdRecord] = PublicKey.findProgramAddressSync(
+    [Buffer.from("unique_id"), uniqueId],
+    program.programId
+  );
+  const [tokenIdRecord] = PublicKey.findProgramAddressSync(
+    [Buffer.from("token_id"), tokenId.toArrayLike(Buffer, "le", 8)],
+    program.programId
+  );
+  return { uniqueIdRecord, tokenIdRecord };
+}
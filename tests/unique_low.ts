@@ -1,6 +1,7 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { UniqueLow } from "../target/types/unique_low";
+import { MockWormhole } from "../target/types/mock_wormhole";
 import {
     PublicKey,
     Keypair,
@@ -16,6 +17,8 @@ import {
     getAccount
 } from "@solana/spl-token";
 import { expect } from "chai";
+import { createHash } from "crypto";
+import { keccak_256 } from "@noble/hashes/sha3";
 
 describe("unique_low", () => {
     // Configure the client to use the local cluster.
@@ -23,6 +26,7 @@ describe("unique_low", () => {
     anchor.setProvider(provider);
 
     const program = anchor.workspace.UniqueLow as Program<UniqueLow>;
+    const wormhole = anchor.workspace.MockWormhole as Program<MockWormhole>;
 
     // Test accounts
     let authority: Keypair;
@@ -50,6 +54,121 @@ describe("unique_low", () => {
     const collectionSymbol = "TNC";
     const baseUri = "https://example.com/metadata/";
 
+    // Message fee charged by the mock core bridge, in lamports
+    const WORMHOLE_FEE = 100;
+
+    // Cross-chain mint contract on Ethereum whose messages are trusted
+    const ETHEREUM_CHAIN_ID = 2;
+    const foreignEmitter = Buffer.alloc(32, 0x42);
+
+    const foreignEmitterPda = (chain: number) => {
+        const chainBytes = Buffer.alloc(2);
+        chainBytes.writeUInt16LE(chain);
+        return PublicKey.findProgramAddressSync([Buffer.from("foreign_emitter"), chainBytes], program.programId)[0];
+    };
+
+    // Core bridge accounts for this program's next message. The message PDA
+    // is keyed by the sequence the bridge assigns next
+    const outboundWormholeAccounts = async () => {
+        const [wormholeEmitter] = PublicKey.findProgramAddressSync([Buffer.from("emitter")], program.programId);
+        const [wormholeSequence] = PublicKey.findProgramAddressSync(
+            [Buffer.from("Sequence"), wormholeEmitter.toBuffer()],
+            wormholeProgram
+        );
+        const sequenceInfo = await provider.connection.getAccountInfo(wormholeSequence);
+        const nextSequence = sequenceInfo ? new anchor.BN(sequenceInfo.data.subarray(0, 8), "le") : new anchor.BN(0);
+        const sequenceBytes = nextSequence.toArrayLike(Buffer, "le", 8);
+        return {
+            wormholeEmitter,
+            wormholeSequence,
+            wormholeMessage: PublicKey.findProgramAddressSync([Buffer.from("message"), sequenceBytes], program.programId)[0],
+            wormholeFeeCollector: PublicKey.findProgramAddressSync([Buffer.from("fee_collector")], wormholeProgram)[0],
+            wormholeProgram,
+            nextSequence,
+        };
+    };
+
+    // abi.encode(uint8 version, uint8 type, bytes32 recipient, bytes32 uniqueId, uint16 targetChainId)
+    const mintPayload = (recipient: PublicKey, uniqueId: Buffer, targetChain: number) => {
+        const word = (value: number) => {
+            const buf = Buffer.alloc(32);
+            buf.writeUInt16BE(value, 30);
+            return buf;
+        };
+        return Buffer.concat([word(1), word(1), recipient.toBuffer(), uniqueId, word(targetChain)]);
+    };
+
+    // mintPayload as a PositionTransfer (type 2), followed by bytes32 vault,
+    // uint64 shares, uint64 assets, uint8 lockTier, int64 lockedUntil
+    const positionPayload = (recipient: PublicKey, uniqueId: Buffer, targetChain: number, vault: PublicKey) => {
+        const payload = mintPayload(recipient, uniqueId, targetChain);
+        payload.writeUInt8(2, 63);
+        const word = (value: number) => new anchor.BN(value).toArrayLike(Buffer, "be", 32);
+        return Buffer.concat([payload, vault.toBuffer(), word(1_000_000), word(1_000_000), word(3), word(0)]);
+    };
+
+    // Posts an Ethereum-emitted VAA through the mock core bridge and returns
+    // its body hash, the key of the PostedVAA account
+    const postVaa = async (emitter: Buffer, sequence: number, payload: Buffer) => {
+        const body = Buffer.alloc(51);
+        body.writeUInt32BE(1_700_000_000, 0); // timestamp
+        body.writeUInt32BE(0, 4); // nonce
+        body.writeUInt16BE(ETHEREUM_CHAIN_ID, 8);
+        emitter.copy(body, 10);
+        new anchor.BN(sequence).toArrayLike(Buffer, "be", 8).copy(body, 42);
+        body.writeUInt8(1, 50); // consistency level
+        const vaaHash = Buffer.from(keccak_256(Buffer.concat([body, payload])));
+
+        await wormhole.methods
+            .postVaa(1_700_000_000, 0, ETHEREUM_CHAIN_ID, Array.from(emitter), new anchor.BN(sequence), 1, payload)
+            .accounts({
+                payer: provider.wallet.publicKey,
+                postedVaa: PublicKey.findProgramAddressSync([Buffer.from("PostedVAA"), vaaHash], wormholeProgram)[0],
+            })
+            .rpc();
+        return vaaHash;
+    };
+
+    const receiveCrossChainMint = async (
+        vaaHash: Buffer,
+        uniqueId: Buffer,
+        recipient: PublicKey,
+        mint: Keypair,
+        tokenId: number
+    ) => {
+        const tokenIdBytes = new anchor.BN(tokenId).toArrayLike(Buffer, "le", 8);
+        return program.methods
+            .receiveCrossChainMint(Array.from(vaaHash))
+            .accountsPartial({
+                collection: collectionPda,
+                wormholeProgram,
+                postedVaa: PublicKey.findProgramAddressSync([Buffer.from("PostedVAA"), vaaHash], wormholeProgram)[0],
+                foreignEmitter: foreignEmitterPda(ETHEREUM_CHAIN_ID),
+                recipient,
+                mint: mint.publicKey,
+                uniqueIdRecord: PublicKey.findProgramAddressSync([Buffer.from("unique_id"), uniqueId], program.programId)[0],
+                tokenIdRecord: PublicKey.findProgramAddressSync([Buffer.from("token_id"), tokenIdBytes], program.programId)[0],
+                payer: provider.wallet.publicKey,
+            })
+            .signers([mint])
+            .rpc();
+    };
+
+    const releaseCrossChainNft = async (vaaHash: Buffer, uniqueIdRecord: PublicKey, mint: PublicKey, recipient: PublicKey) =>
+        program.methods
+            .releaseCrossChainNft(Array.from(vaaHash))
+            .accountsPartial({
+                collection: collectionPda,
+                wormholeProgram,
+                postedVaa: PublicKey.findProgramAddressSync([Buffer.from("PostedVAA"), vaaHash], wormholeProgram)[0],
+                foreignEmitter: foreignEmitterPda(ETHEREUM_CHAIN_ID),
+                uniqueIdRecord,
+                mint,
+                recipient,
+                payer: provider.wallet.publicKey,
+            })
+            .rpc();
+
     before(async () => {
         // Initialize test keypairs
         authority = Keypair.generate();
@@ -66,12 +185,19 @@ describe("unique_low", () => {
 
         // Find collection PDA
         [collectionPda, collectionBump] = PublicKey.findProgramAddressSync(
-            [Buffer.from("collection")],
+            [Buffer.from("collection_v2")],
             program.programId
         );
 
-        // Wormhole program ID (using placeholder)
-        wormholeProgram = new PublicKey("worm2ZoG2kUd4vFXhvjh93UUH596ayRfgQ2MgjNMTth");
+        // Local stand-in for the Wormhole core bridge, charging a message fee
+        wormholeProgram = wormhole.programId;
+        const [bridgeConfig] = PublicKey.findProgramAddressSync([Buffer.from("Bridge")], wormholeProgram);
+        if (!(await provider.connection.getAccountInfo(bridgeConfig))) {
+            await wormhole.methods
+                .initialize(new anchor.BN(WORMHOLE_FEE))
+                .accounts({ payer: provider.wallet.publicKey })
+                .rpc();
+        }
     });
 
     it("Initialize collection", async () => {
@@ -114,7 +240,7 @@ describe("unique_low", () => {
 
         // Find user state PDA
         const [userStatePda] = PublicKey.findProgramAddressSync(
-            [Buffer.from("user_state"), user1.publicKey.toBuffer()],
+            [Buffer.from("user_state_v2"), user1.publicKey.toBuffer()],
             program.programId
         );
 
@@ -132,6 +258,8 @@ describe("unique_low", () => {
 
                 user: user1.publicKey,
 
+                ...(await registryAccounts(program, user1.publicKey)),
+
             })
             .signers([user1, mint])
             .rpc();
@@ -162,7 +290,7 @@ describe("unique_low", () => {
 
         // Find user state PDA (same as before)
         const [userStatePda] = PublicKey.findProgramAddressSync(
-            [Buffer.from("user_state"), user1.publicKey.toBuffer()],
+            [Buffer.from("user_state_v2"), user1.publicKey.toBuffer()],
             program.programId
         );
 
@@ -180,6 +308,8 @@ describe("unique_low", () => {
 
                 user: user1.publicKey,
 
+                ...(await registryAccounts(program, user1.publicKey)),
+
             })
             .signers([user1, mint2])
             .rpc();
@@ -201,7 +331,7 @@ describe("unique_low", () => {
 
         // Find user state PDA for user2
         const [userStatePda] = PublicKey.findProgramAddressSync(
-            [Buffer.from("user_state"), user2.publicKey.toBuffer()],
+            [Buffer.from("user_state_v2"), user2.publicKey.toBuffer()],
             program.programId
         );
 
@@ -219,6 +349,8 @@ describe("unique_low", () => {
 
                 user: user2.publicKey,
 
+                ...(await registryAccounts(program, user2.publicKey)),
+
 
             })
             .signers([user2, mint])
@@ -231,35 +363,437 @@ describe("unique_low", () => {
         user2Data.firstTokenAccount = tokenAccount;
     });
 
-    it("Request cross-chain mint (mock)", async () => {
-        // CHECK IF user1Data is properly initialized
-        if (!user1Data.firstMint || !user1Data.firstTokenAccount) {
-            console.log("Skipping cross-chain test - user1Data not initialized");
-            return;
+    it("Mint NFT with metadata, master edition and verified collection", async () => {
+        // Needs the metadata program loaded into the validator, see script.txt
+        const metadataProgram = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+        const mint = Keypair.generate();
+        const [metadataPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("metadata"), metadataProgram.toBuffer(), mint.publicKey.toBuffer()],
+            metadataProgram
+        );
+        const [masterEditionPda] = PublicKey.findProgramAddressSync(
+            [
+                Buffer.from("metadata"),
+                metadataProgram.toBuffer(),
+                mint.publicKey.toBuffer(),
+                Buffer.from("edition"),
+            ],
+            metadataProgram
+        );
+        const [collectionMint] = PublicKey.findProgramAddressSync(
+            [Buffer.from("collection_mint")],
+            program.programId
+        );
+        const registry = await registryAccounts(program, user2.publicKey);
+        const collectionBefore = await program.account.collection.fetch(collectionPda);
+        const tokenId = collectionBefore.totalSupply.addn(1).toString();
+
+        await program.methods
+            .mintNftWithMetadata()
+            .accounts({
+                mint: mint.publicKey,
+                user: user2.publicKey,
+                ...registry,
+            })
+            .signers([user2, mint])
+            .rpc();
+
+        // Metadata layout: key (1), update authority (32), mint (32), then
+        // name, symbol and uri as u32-length-prefixed, NUL-padded strings
+        const metadata = await provider.connection.getAccountInfo(metadataPda);
+        expect(metadata).to.not.be.null;
+        let offset = 1 + 32 + 32;
+        const readString = () => {
+            const len = metadata!.data.readUInt32LE(offset);
+            const value = metadata!.data
+                .subarray(offset + 4, offset + 4 + len)
+                .toString("utf8")
+                .replace(/\0/g, "");
+            offset += 4 + len;
+            return value;
+        };
+        expect(metadata!.data.subarray(1, 33).equals(collectionPda.toBuffer())).to.equal(true);
+        expect(readString()).to.equal(`${collectionBefore.name} #${tokenId}`);
+        expect(readString()).to.equal(collectionBefore.symbol);
+        expect(readString()).to.equal(`${collectionBefore.baseUri}${tokenId}.json`);
+        expect(metadata!.data.readUInt16LE(offset)).to.equal(500);
+        offset += 2;
+
+        // creators: Option<Vec<{ address, verified, share }>>
+        if (metadata!.data[offset++] === 1) {
+            offset += 4 + metadata!.data.readUInt32LE(offset) * 34;
+        }
+        offset += 2; // primary_sale_happened, is_mutable
+        for (let i = 0; i < 2; i++) {
+            // edition_nonce, token_standard: Option<u8>
+            offset += metadata!.data[offset] === 1 ? 2 : 1;
+        }
+        // collection: Option<{ verified, key }>
+        expect(metadata!.data[offset]).to.equal(1);
+        expect(metadata!.data[offset + 1]).to.equal(1);
+        expect(
+            metadata!.data.subarray(offset + 2, offset + 34).equals(collectionMint.toBuffer())
+        ).to.equal(true);
+
+        // The master edition took over the mint, so supply is locked at 1
+        const mintInfo = await provider.connection.getParsedAccountInfo(mint.publicKey);
+        const mintData = mintInfo.value?.data;
+        if (mintData && "parsed" in mintData) {
+            expect(mintData.parsed.info.mintAuthority).to.equal(masterEditionPda.toString());
+            expect(mintData.parsed.info.supply).to.equal("1");
         }
+    });
 
-        console.log("Note: Cross-chain minting requires Wormhole integration setup");
-        console.log("User1 first mint:", user1Data.firstMint.toString());
-        console.log("User1 token account:", user1Data.firstTokenAccount.toString());
+    it("Registry records resolve by mint, unique ID and token ID", async () => {
+        const [mintRecordPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("mint_record"), user2Data.firstMint!.toBuffer()],
+            program.programId
+        );
+        const mintRecord = await program.account.mintRecord.fetch(mintRecordPda);
+        expect(mintRecord.mint.toString()).to.equal(user2Data.firstMint!.toString());
 
-        // The actual test would look like this (commented out due to Wormhole dependency):
-        /*
-        const tx = await program.methods
-          .requestCrossChainMint(
-            1, // nonce
-            2, // target_chain_id (Ethereum)
-            Array.from(Buffer.alloc(32, 1)) // recipient address
-          )
-          .accounts({
-            collection: collectionPda,
-            mint: user1Data.firstMint,
-            tokenAccount: user1Data.firstTokenAccount,
-            user: user1.publicKey,
-            // ... wormhole accounts
-          })
-          .signers([user1])
-          .rpc();
-        */
+        const exists = await program.methods
+            .uniqueIdExists(mintRecord.uniqueId)
+            .view();
+        expect(exists).to.equal(true);
+
+        const tokenId = await program.methods
+            .getTokenIdByUniqueId(mintRecord.uniqueId)
+            .view();
+        expect(tokenId.toString()).to.equal(mintRecord.tokenId.toString());
+
+        const uniqueId = await program.methods
+            .getUniqueIdByTokenId(mintRecord.tokenId)
+            .view();
+        expect(Buffer.from(uniqueId).equals(Buffer.from(mintRecord.uniqueId))).to.equal(true);
+
+        const missing = await program.methods
+            .uniqueIdExists(Array.from(Buffer.alloc(32, 7)))
+            .view();
+        expect(missing).to.equal(false);
+    });
+
+    it("Register a foreign emitter as a non-authority (should fail)", async () => {
+        try {
+            await program.methods
+                .registerEmitter(ETHEREUM_CHAIN_ID, Array.from(foreignEmitter))
+                .accounts({ authority: user1.publicKey })
+                .signers([user1])
+                .rpc();
+            expect.fail("Non-authority registered an emitter");
+        } catch (err) {
+            expect(err.toString()).to.include("Unauthorized");
+        }
+    });
+
+    it("Register a foreign emitter", async () => {
+        await program.methods
+            .registerEmitter(ETHEREUM_CHAIN_ID, Array.from(foreignEmitter))
+            .accounts({ authority: authority.publicKey })
+            .signers([authority])
+            .rpc();
+
+        const emitter = await program.account.foreignEmitter.fetch(foreignEmitterPda(ETHEREUM_CHAIN_ID));
+        expect(emitter.chain).to.equal(ETHEREUM_CHAIN_ID);
+        expect(Buffer.from(emitter.address).equals(foreignEmitter)).to.equal(true);
+    });
+
+    it("Request a cross-chain transfer without the vault's signature (should fail)", async () => {
+        const { nextSequence, ...wormholeAccounts } = await outboundWormholeAccounts();
+        const forger = Keypair.generate();
+        try {
+            await program.methods
+                .requestCrossChainTransfer(7, ETHEREUM_CHAIN_ID, Array.from(Buffer.alloc(32, 1)), {
+                    vault: forger.publicKey,
+                    shares: new anchor.BN(1_000_000),
+                    assets: new anchor.BN(1_000_000_000),
+                    lockTier: 3,
+                    lockedUntil: new anchor.BN(0),
+                })
+                .accountsPartial({
+                    request: {
+                        collection: collectionPda,
+                        foreignEmitter: foreignEmitterPda(ETHEREUM_CHAIN_ID),
+                        mint: user1Data.firstMint,
+                        tokenAccount: user1Data.firstTokenAccount,
+                        user: user1.publicKey,
+                        ...wormholeAccounts,
+                    },
+                    positionAuthority: forger.publicKey,
+                })
+                .signers([user1, forger])
+                .rpc();
+            expect.fail("Attached a position snapshot without simple_vault");
+        } catch (err) {
+            expect(err.toString()).to.include("ConstraintSeeds");
+        }
+    });
+
+    it("Request cross-chain mint posts a Wormhole message", async () => {
+        const recipient = Buffer.alloc(32, 1);
+        const targetChainId = ETHEREUM_CHAIN_ID;
+
+        const {
+            wormholeEmitter: emitter,
+            wormholeSequence: sequence,
+            wormholeMessage: message,
+            wormholeFeeCollector: feeCollector,
+            nextSequence,
+        } = await outboundWormholeAccounts();
+        const feesBefore = await provider.connection.getBalance(feeCollector);
+
+        await program.methods
+            .requestCrossChainMint(7, targetChainId, Array.from(recipient))
+            .accountsPartial({
+                collection: collectionPda,
+                mint: user1Data.firstMint,
+                tokenAccount: user1Data.firstTokenAccount,
+                user: user1.publicKey,
+                foreignEmitter: foreignEmitterPda(targetChainId),
+                wormholeEmitter: emitter,
+                wormholeSequence: sequence,
+                wormholeMessage: message,
+                wormholeFeeCollector: feeCollector,
+                wormholeProgram,
+            })
+            .signers([user1])
+            .rpc();
+
+        expect(await provider.connection.getBalance(feeCollector)).to.equal(feesBefore + WORMHOLE_FEE);
+        const sequenceAfter = await provider.connection.getAccountInfo(sequence);
+        expect(new anchor.BN(sequenceAfter.data.subarray(0, 8), "le").toString()).to.equal(nextSequence.addn(1).toString());
+
+        // "msg" ++ MessageData: the emitter is at 59..91, the payload length at 91
+        const posted = (await provider.connection.getAccountInfo(message)).data;
+        expect(posted.subarray(0, 3).toString()).to.equal("msg");
+        expect(posted.readUInt32LE(45)).to.equal(7);
+        expect(new anchor.BN(posted.subarray(49, 57), "le").toString()).to.equal(nextSequence.toString());
+        expect(new PublicKey(posted.subarray(59, 91)).toBase58()).to.equal(emitter.toBase58());
+
+        const mintRecord = await program.account.mintRecord.fetch(
+            PublicKey.findProgramAddressSync(
+                [Buffer.from("mint_record"), user1Data.firstMint.toBuffer()],
+                program.programId
+            )[0]
+        );
+        const payload = posted.subarray(95, 95 + posted.readUInt32LE(91));
+        expect(payload.length).to.equal(160);
+        expect(payload[31]).to.equal(1); // version
+        expect(payload[63]).to.equal(1); // MintRequest
+        expect(payload.subarray(64, 96).equals(recipient)).to.equal(true);
+        expect(payload.subarray(96, 128).equals(Buffer.from(mintRecord.uniqueId))).to.equal(true);
+        expect(payload.readUInt16BE(158)).to.equal(targetChainId);
+
+        const record = await program.account.uniqueIdRecord.fetch(
+            PublicKey.findProgramAddressSync(
+                [Buffer.from("unique_id"), Buffer.from(mintRecord.uniqueId)],
+                program.programId
+            )[0]
+        );
+        expect(record.crossChain).to.equal(true);
+
+        // The NFT waits in the collection's escrow while it lives on Ethereum
+        const escrow = await getAccount(
+            provider.connection,
+            await getAssociatedTokenAddress(user1Data.firstMint, collectionPda, true)
+        );
+        expect(escrow.amount.toString()).to.equal("1");
+        const holder = await getAccount(provider.connection, user1Data.firstTokenAccount);
+        expect(holder.amount.toString()).to.equal("0");
+    });
+
+    it("Receive a cross-chain mint from the registered emitter", async () => {
+        const uniqueId = Buffer.alloc(32, 0xab);
+        const vaaHash = await postVaa(foreignEmitter, 0, mintPayload(user2.publicKey, uniqueId, 1));
+        const mint = Keypair.generate();
+        const collectionBefore = await program.account.collection.fetch(collectionPda);
+        const tokenId = collectionBefore.totalSupply.addn(1);
+
+        await receiveCrossChainMint(vaaHash, uniqueId, user2.publicKey, mint, tokenId.toNumber());
+
+        const tokenAccount = await getAccount(
+            provider.connection,
+            await getAssociatedTokenAddress(mint.publicKey, user2.publicKey)
+        );
+        expect(tokenAccount.amount.toString()).to.equal("1");
+
+        const record = await program.account.uniqueIdRecord.fetch(
+            PublicKey.findProgramAddressSync([Buffer.from("unique_id"), uniqueId], program.programId)[0]
+        );
+        expect(record.mint.toBase58()).to.equal(mint.publicKey.toBase58());
+        expect(record.tokenId.toString()).to.equal(tokenId.toString());
+        expect(record.crossChain).to.equal(false);
+
+        // The same VAA cannot be redeemed twice
+        try {
+            await receiveCrossChainMint(vaaHash, uniqueId, user2.publicKey, Keypair.generate(), tokenId.toNumber() + 1);
+            expect.fail("Redeemed a VAA twice");
+        } catch (err) {
+            expect(err.toString()).to.include("already in use");
+        }
+    });
+
+    it("Receive a cross-chain mint from an unregistered emitter (should fail)", async () => {
+        const uniqueId = Buffer.alloc(32, 0xcd);
+        const vaaHash = await postVaa(Buffer.alloc(32, 0x66), 0, mintPayload(user2.publicKey, uniqueId, 1));
+        const collection = await program.account.collection.fetch(collectionPda);
+        try {
+            await receiveCrossChainMint(
+                vaaHash,
+                uniqueId,
+                user2.publicKey,
+                Keypair.generate(),
+                collection.totalSupply.toNumber() + 1
+            );
+            expect.fail("Accepted a VAA from an unregistered emitter");
+        } catch (err) {
+            expect(err.toString()).to.include("UnknownEmitter");
+        }
+    });
+
+    it("Receive a cross-chain mint for another recipient (should fail)", async () => {
+        const uniqueId = Buffer.alloc(32, 0xef);
+        const vaaHash = await postVaa(foreignEmitter, 1, mintPayload(user2.publicKey, uniqueId, 1));
+        const collection = await program.account.collection.fetch(collectionPda);
+        try {
+            await receiveCrossChainMint(
+                vaaHash,
+                uniqueId,
+                user1.publicKey,
+                Keypair.generate(),
+                collection.totalSupply.toNumber() + 1
+            );
+            expect.fail("Minted to a wallet the payload does not name");
+        } catch (err) {
+            expect(err.toString()).to.include("RecipientMismatch");
+        }
+    });
+
+    it("Receive a cross-chain mint addressed to another chain (should fail)", async () => {
+        const uniqueId = Buffer.alloc(32, 0xa1);
+        const vaaHash = await postVaa(foreignEmitter, 4, mintPayload(user2.publicKey, uniqueId, ETHEREUM_CHAIN_ID));
+        const collection = await program.account.collection.fetch(collectionPda);
+        try {
+            await receiveCrossChainMint(
+                vaaHash,
+                uniqueId,
+                user2.publicKey,
+                Keypair.generate(),
+                collection.totalSupply.toNumber() + 1
+            );
+            expect.fail("Minted from a VAA addressed to Ethereum");
+        } catch (err) {
+            expect(err.toString()).to.include("WrongTargetChain");
+        }
+    });
+
+    it("Receive a position transfer as a new mint (should fail)", async () => {
+        const uniqueId = Buffer.alloc(32, 0xa2);
+        const vaaHash = await postVaa(
+            foreignEmitter,
+            5,
+            positionPayload(user2.publicKey, uniqueId, 1, Keypair.generate().publicKey)
+        );
+        const collection = await program.account.collection.fetch(collectionPda);
+        try {
+            await receiveCrossChainMint(
+                vaaHash,
+                uniqueId,
+                user2.publicKey,
+                Keypair.generate(),
+                collection.totalSupply.toNumber() + 1
+            );
+            expect.fail("Minted a new NFT for a returning position");
+        } catch (err) {
+            expect(err.toString()).to.include("InvalidPayload");
+        }
+    });
+
+    it("Release an escrowed NFT when it returns from Ethereum", async () => {
+        const mintRecord = await program.account.mintRecord.fetch(
+            PublicKey.findProgramAddressSync(
+                [Buffer.from("mint_record"), user1Data.firstMint.toBuffer()],
+                program.programId
+            )[0]
+        );
+        const uniqueId = Buffer.from(mintRecord.uniqueId);
+        const [uniqueIdRecord] = PublicKey.findProgramAddressSync(
+            [Buffer.from("unique_id"), uniqueId],
+            program.programId
+        );
+        const vaaHash = await postVaa(foreignEmitter, 2, mintPayload(user1.publicKey, uniqueId, 1));
+
+        await releaseCrossChainNft(vaaHash, uniqueIdRecord, user1Data.firstMint, user1.publicKey);
+
+        const holder = await getAccount(provider.connection, user1Data.firstTokenAccount);
+        expect(holder.amount.toString()).to.equal("1");
+        const record = await program.account.uniqueIdRecord.fetch(uniqueIdRecord);
+        expect(record.crossChain).to.equal(false);
+
+        // The same VAA cannot be redeemed twice
+        try {
+            await releaseCrossChainNft(vaaHash, uniqueIdRecord, user1Data.firstMint, user1.publicKey);
+            expect.fail("Redeemed a VAA twice");
+        } catch (err) {
+            expect(err.toString()).to.include("already in use");
+        }
+
+        // A second release of the same NFT has nothing left in escrow
+        const replay = await postVaa(foreignEmitter, 3, mintPayload(user1.publicKey, uniqueId, 1));
+        try {
+            await releaseCrossChainNft(replay, uniqueIdRecord, user1Data.firstMint, user1.publicKey);
+            expect.fail("Released an NFT that was never bridged out");
+        } catch (err) {
+            expect(err.toString()).to.include("NotBridgedOut");
+        }
+    });
+
+    it("Deregister a foreign emitter", async () => {
+        await program.methods
+            .deregisterEmitter(ETHEREUM_CHAIN_ID)
+            .accounts({ authority: authority.publicKey })
+            .signers([authority])
+            .rpc();
+        expect(await provider.connection.getAccountInfo(foreignEmitterPda(ETHEREUM_CHAIN_ID))).to.be.null;
+
+        // Without a trusted contract on the target chain nothing is sent
+        const { nextSequence, ...wormholeAccounts } = await outboundWormholeAccounts();
+        try {
+            await program.methods
+                .requestCrossChainMint(8, ETHEREUM_CHAIN_ID, Array.from(Buffer.alloc(32, 1)))
+                .accountsPartial({
+                    collection: collectionPda,
+                    foreignEmitter: foreignEmitterPda(ETHEREUM_CHAIN_ID),
+                    mint: user1Data.firstMint,
+                    tokenAccount: user1Data.firstTokenAccount,
+                    user: user1.publicKey,
+                    ...wormholeAccounts,
+                })
+                .signers([user1])
+                .rpc();
+            expect.fail("Sent a cross-chain mint to a chain without an emitter");
+        } catch (err) {
+            expect(err.toString()).to.include("AccountNotInitialized");
+        }
+
+        await program.methods
+            .registerEmitter(ETHEREUM_CHAIN_ID, Array.from(foreignEmitter))
+            .accounts({ authority: authority.publicKey })
+            .signers([authority])
+            .rpc();
+    });
+
+    it("Register an emitter for Solana itself (should fail)", async () => {
+        try {
+            await program.methods
+                .registerEmitter(1, Array.from(foreignEmitter))
+                .accounts({ authority: authority.publicKey })
+                .signers([authority])
+                .rpc();
+            expect.fail("Registered an emitter for Solana");
+        } catch (err) {
+            expect(err.toString()).to.include("UnsupportedChain");
+        }
     });
 
     it("Verify NFT ownership and mint authority", async () => {
@@ -285,7 +819,7 @@ describe("unique_low", () => {
         try {
             // Find user state PDA for user1
             const [userStatePda] = PublicKey.findProgramAddressSync(
-                [Buffer.from("user_state"), user1.publicKey.toBuffer()],
+                [Buffer.from("user_state_v2"), user1.publicKey.toBuffer()],
                 program.programId
             );
 
@@ -322,4 +856,39 @@ describe("unique_low", () => {
 // Helper function to add to the test file if needed
 function sleep(ms: number): Promise<void> {
     return new Promise(resolve => setTimeout(resolve, ms));
-}
\ No newline at end of file
+}
+
+// mint_nft creates registry records seeded by the minter's next unique ID
+// (sha256(chainId, wallet, nonce), big endian) and the next token ID.
+async function registryAccounts(program: Program<UniqueLow>, user: PublicKey) {
+    const [userStatePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_state_v2"), user.toBuffer()],
+        program.programId
+    );
+    const [collectionPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("collection_v2")],
+        program.programId
+    );
+    const userState = await program.account.userState.fetchNullable(userStatePda);
+    const collection = await program.account.collection.fetch(collectionPda);
+
+    const nonce = userState ? userState.nonce : new anchor.BN(0);
+    const uniqueId = createHash("sha256")
+        .update(Buffer.concat([
+            new anchor.BN(1).toArrayLike(Buffer, "be", 8),
+            user.toBuffer(),
+            nonce.toArrayLike(Buffer, "be", 8),
+        ]))
+        .digest();
+    const tokenId = collection.totalSupply.addn(1);
+
+    const [uniqueIdRecord] = PublicKey.findProgramAddressSync(
+        [Buffer.from("unique_id"), uniqueId],
+        program.programId
+    );
+    const [tokenIdRecord] = PublicKey.findProgramAddressSync(
+        [Buffer.from("token_id"), tokenId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+    );
+    return { uniqueIdRecord, tokenIdRecord };
+}